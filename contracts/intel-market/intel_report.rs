@@ -3,48 +3,1281 @@
 // This schema is minimal and secure, ready for extension as needed.
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, TransferChecked};
 
+// IntelReport PDA, seeds = ["intel_report", author, sequence (u64 LE)]
 #[account]
 pub struct IntelReport {
-    pub title: String,         // Title of the report
-    pub content: String,       // Main content (redact as needed)
-    pub tags: Vec<String>,     // Tags (SIGINT, HUMINT, etc.)
-    pub latitude: f64,         // Geolocation (lat)
-    pub longitude: f64,        // Geolocation (lng)
-    pub timestamp: i64,        // Unix timestamp
-    pub author: Pubkey,        // Author (wallet address)
+    pub version: u8,      // Layout version, IntelReport::VERSION for current accounts
+    pub geohash: [u8; 8], // Base32 geohash of the location; leads so memcmp can filter by prefix
+    pub title: String,    // Title of the report
+    pub content: ReportContent, // Main content, inline or as a hash of an off-chain body
+    pub tags: Vec<u16>,   // Registered tag IDs (SIGINT, HUMINT, etc.), see Tag
+    pub latitude: i32,    // Geolocation (lat), microdegrees rounded to `location_precision`
+    pub longitude: i32,   // Geolocation (lng), microdegrees rounded to `location_precision`
+    pub location_precision: LocationPrecision, // How coarsely the author chose to publish the location
+    pub observed_at: i64, // When the reported activity was observed (author-supplied)
+    pub created_at: i64,  // When the report was written, from the on-chain clock
+    pub author: Pubkey,   // Author (wallet address)
+    pub revision: u32,    // 1 for an original report, +1 for each superseding report
+    pub previous: Option<Pubkey>, // Report this one supersedes, if any
+    pub superseded_by: Option<Pubkey>, // Newer report that replaces this one, if any
+    pub sequence: u64,    // Per-author report number, part of the PDA seeds
+    pub bump: u8,
+    pub classification: Classification, // Handling level of the report
+    pub team: Option<Pubkey>,           // CyberTeam the report is restricted to, if any
+    pub geo_indexed: bool,              // Counted in the GeoCell for its geohash prefix
 }
 
+// Classification levels, matching the TS `classification` enums
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+pub enum Classification {
+    Unclassified,
+    Confidential,
+    Secret,
+    TopSecret,
+}
+
+impl IntelReport {
+    pub const SEED: &'static [u8] = b"intel_report";
+    pub const MAX_TITLE_LEN: usize = 256;
+    pub const MAX_CONTENT_LEN: usize = 1024; // Default for ProgramConfig::max_content_len
+    pub const MAX_TAGS: usize = 16;
+    pub const MAX_LATITUDE: i32 = 90_000_000; // Microdegrees
+    pub const MAX_LONGITUDE: i32 = 180_000_000;
+    // Tolerated drift between the author's clock and the cluster clock for `observed_at`
+    pub const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+    pub const GEOHASH_LEN: usize = 8; // ~19m x 19m cells
+    pub const GEOHASH_OFFSET: usize = 9; // After the discriminator and version
+    pub const VERSION: u8 = 2;
+    // Largest account less its body: a full title and tag list, plus an encrypted body's
+    // key, nonce, AEAD tag and a full set of envelopes
+    pub const MAX_SPACE_WITHOUT_BODY: usize = 8
+        + 1
+        + Self::GEOHASH_LEN
+        + (4 + Self::MAX_TITLE_LEN)
+        + (1 + 32 + 24 + 4 + ReportContent::AEAD_TAG_LEN)
+        + (4 + ReportContent::MAX_ENVELOPES * KeyEnvelope::SPACE)
+        + (4 + 2 * Self::MAX_TAGS)
+        + 4
+        + 4
+        + 1
+        + 8
+        + 8
+        + 32
+        + 4
+        + (1 + 32)
+        + (1 + 32)
+        + 8
+        + 1
+        + 1
+        + (1 + 32)
+        + 1;
+
+    // Exact Borsh size for the given inputs, including the 4-byte length prefix of
+    // every String and Vec, so the account is never over- or under-allocated.
+    pub fn space(title: &str, content: &ReportContent, tags: &[u16]) -> usize {
+        8 // discriminator
+            + 1 // version
+            + Self::GEOHASH_LEN
+            + 4 + title.len()
+            + content.space()
+            + 4 + 2 * tags.len()
+            + 4 + 4 // latitude, longitude
+            + 1 // location_precision
+            + 8 + 8 // observed_at, created_at
+            + 32 // author
+            + 4 // revision
+            + 1 + 32 // previous
+            + 1 + 32 // superseded_by
+            + 8 // sequence
+            + 1 // bump
+            + 1 // classification
+            + 1 + 32 // team
+            + 1 // geo_indexed
+    }
+
+    // Space for a new report. Used by the init constraints, so a report too large for one
+    // allocation gets ContentTooLong instead of failing in the system program.
+    pub fn allocation_space(title: &str, content: &ReportContent, tags: &[u16]) -> Result<usize> {
+        let space = Self::space(title, content, tags);
+        require!(
+            space <= MAX_PERMITTED_DATA_INCREASE,
+            IntelMarketError::ContentTooLong
+        );
+        Ok(space)
+    }
+}
+
+// Grid the published location must lie on. Coarser levels hide the exact position of
+// sensitive sources. Clients snap coordinates with `coarsen` before sending them, because
+// instruction data is public: snapping on-chain would still publish the exact position in
+// the transaction. Off-grid coordinates are rejected.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LocationPrecision {
+    Exact,        // 0.000001 degrees, ~0.1m
+    Street,       // 0.0001 degrees, ~11m
+    Neighborhood, // 0.001 degrees, ~110m
+    City,         // 0.01 degrees, ~1.1km
+    Region,       // 0.1 degrees, ~11km
+    Country,      // 1 degree, ~111km
+}
+
+impl LocationPrecision {
+    pub fn step_microdegrees(self) -> i32 {
+        match self {
+            LocationPrecision::Exact => 1,
+            LocationPrecision::Street => 100,
+            LocationPrecision::Neighborhood => 1_000,
+            LocationPrecision::City => 10_000,
+            LocationPrecision::Region => 100_000,
+            LocationPrecision::Country => 1_000_000,
+        }
+    }
+
+    // Rounds to the nearest grid point, staying within [-max, max]. For clients; the range
+    // limits are multiples of every step, so the result is always on the grid.
+    pub fn coarsen(self, microdegrees: i32, max: i32) -> i32 {
+        let step = self.step_microdegrees() as i64;
+        let snapped = (microdegrees as i64 + step / 2).div_euclid(step) * step;
+        snapped.clamp(-(max as i64), max as i64) as i32
+    }
+
+    pub fn is_on_grid(self, microdegrees: i32) -> bool {
+        microdegrees % self.step_microdegrees() == 0
+    }
+}
+
+// Geohash alphabet; a cell's geohash is a prefix of every geohash inside it
+pub const GEOHASH_BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+// Standard geohash encoding, alternating longitude and latitude bits starting with longitude
+pub fn geohash_encode(latitude: i32, longitude: i32) -> [u8; IntelReport::GEOHASH_LEN] {
+    let (latitude, longitude) = (latitude as f64 / 1e6, longitude as f64 / 1e6);
+    let (mut lat_range, mut lng_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut geohash = [0u8; IntelReport::GEOHASH_LEN];
+    let mut even = true;
+    for c in geohash.iter_mut() {
+        let mut index = 0usize;
+        for _ in 0..5 {
+            let (range, value): (&mut (f64, f64), f64) = if even {
+                (&mut lng_range, longitude)
+            } else {
+                (&mut lat_range, latitude)
+            };
+            let mid = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= mid {
+                index |= 1;
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+        *c = GEOHASH_BASE32[index];
+    }
+    geohash
+}
+
+// Report body storage. Inline bodies are public and pay rent per byte; off-chain
+// bodies live on IPFS (via RelayNodeIPFSService) and only their digest is kept here.
+// Encrypted bodies are for team-restricted reports: the body is sealed under a random
+// content key, and that key is wrapped once per team member (see the `client` module).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum ReportContent {
+    Inline(String), // Plaintext body (redact as needed)
+    OffChain {
+        cid: String,      // IPFS CID where the body is pinned
+        sha256: [u8; 32], // SHA-256 digest of the body bytes
+        length: u64,      // Body length in bytes
+    },
+    Encrypted {
+        ephemeral_key: [u8; 32], // X25519 public key the envelopes were wrapped with
+        nonce: [u8; 24],         // XChaCha20-Poly1305 nonce for the body
+        ciphertext: Vec<u8>,     // Sealed body, including the 16-byte tag
+        envelopes: Vec<KeyEnvelope>, // One wrapped content key per member
+    },
+}
+
+// The body's content key, wrapped to one member's wallet key
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct KeyEnvelope {
+    pub recipient: Pubkey,
+    pub sealed_key: [u8; 48], // ChaCha20-Poly1305 sealed 32-byte key
+}
+
+impl KeyEnvelope {
+    pub const SPACE: usize = 32 + 48;
+}
+
+impl ReportContent {
+    pub const MAX_CID_LEN: usize = 64;
+    pub const MAX_ENVELOPES: usize = 32;
+    pub const AEAD_TAG_LEN: usize = 16;
+
+    // Borsh size including the enum variant tag
+    pub fn space(&self) -> usize {
+        1 + match self {
+            ReportContent::Inline(body) => 4 + body.len(),
+            ReportContent::OffChain { cid, .. } => 4 + cid.len() + 32 + 8,
+            ReportContent::Encrypted {
+                ciphertext,
+                envelopes,
+                ..
+            } => 32 + 24 + 4 + ciphertext.len() + 4 + envelopes.len() * KeyEnvelope::SPACE,
+        }
+    }
+
+    // Confirms that a payload fetched off-chain matches what the report committed to.
+    // Inline bodies are compared byte for byte, encrypted ones against the ciphertext.
+    pub fn verify(&self, payload: &[u8]) -> bool {
+        match self {
+            ReportContent::Inline(body) => body.as_bytes() == payload,
+            ReportContent::Encrypted { ciphertext, .. } => ciphertext.as_slice() == payload,
+            ReportContent::OffChain { sha256, length, .. } => {
+                payload.len() as u64 == *length
+                    && anchor_lang::solana_program::hash::hash(payload).to_bytes() == *sha256
+            }
+        }
+    }
+}
+
+// Per-author state PDA, seeds = ["author_state", author].
+// `report_count` is the sequence number the author's next report must use.
+// The remaining fields drive the per-author rate limit configured in ProgramConfig.
+#[account]
+pub struct AuthorState {
+    pub author: Pubkey,
+    pub report_count: u64,
+    pub bump: u8,
+    pub last_report_at: i64, // Creation time of the author's latest report, 0 before the first
+    // Creation times of the author's latest reports, oldest overwritten first; 0 is unused
+    pub recent_reports: [i64; AuthorState::MAX_DAILY_REPORT_CAP],
+}
+
+impl AuthorState {
+    pub const SEED: &'static [u8] = b"author_state";
+    // Highest daily_report_cap the config accepts; as many report times are kept
+    pub const MAX_DAILY_REPORT_CAP: usize = 24;
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 8 + 8 * Self::MAX_DAILY_REPORT_CAP;
+    pub const WINDOW_SECS: i64 = 24 * 60 * 60;
+
+    // Counts a new report at `now`, enforcing the config's limits (0 disables a limit).
+    // The cap applies to every 24h span ending now, not to fixed days, so a burst at the
+    // end of one day cannot be followed by another at the start of the next.
+    pub fn record_report(&mut self, config: &ProgramConfig, now: i64) -> Result<()> {
+        if self.last_report_at != 0 {
+            require!(
+                now >= self.last_report_at + config.min_report_interval as i64,
+                IntelMarketError::ReportRateLimited
+            );
+        }
+        let in_window = self
+            .recent_reports
+            .iter()
+            .filter(|at| **at != 0 && **at > now - Self::WINDOW_SECS)
+            .count();
+        require!(
+            config.daily_report_cap == 0 || in_window < config.daily_report_cap as usize,
+            IntelMarketError::DailyReportCapReached
+        );
+        if let Some(oldest) = self.recent_reports.iter_mut().min() {
+            *oldest = now;
+        }
+        self.last_report_at = now;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeAuthorState<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = author,
+        space = AuthorState::SPACE,
+        seeds = [AuthorState::SEED, author.key().as_ref()],
+        bump
+    )]
+    pub author_state: Account<'info, AuthorState>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_author_state(ctx: Context<InitializeAuthorState>) -> Result<()> {
+    let state = &mut ctx.accounts.author_state;
+    state.author = *ctx.accounts.author.key;
+    state.report_count = 0;
+    state.bump = ctx.bumps.author_state;
+    state.last_report_at = 0;
+    state.recent_reports = [0; AuthorState::MAX_DAILY_REPORT_CAP];
+    Ok(())
+}
+
+// Reject malformed input up front with a specific error instead of a generic
+// serialization failure once the account is written.
+pub fn validate_report_fields(
+    config: &ProgramConfig,
+    title: &str,
+    content: &ReportContent,
+    tags: &[u16],
+    latitude: i32,
+    longitude: i32,
+    precision: LocationPrecision,
+) -> Result<()> {
+    require!(
+        title.len() <= IntelReport::MAX_TITLE_LEN,
+        IntelMarketError::TitleTooLong
+    );
+    match content {
+        ReportContent::Inline(body) => {
+            require!(
+                body.len() <= config.max_content_len as usize,
+                IntelMarketError::ContentTooLong
+            );
+        }
+        ReportContent::OffChain { cid, .. } => {
+            require!(
+                cid.len() <= ReportContent::MAX_CID_LEN,
+                IntelMarketError::CidTooLong
+            );
+        }
+        ReportContent::Encrypted {
+            ciphertext,
+            envelopes,
+            ..
+        } => {
+            require!(
+                ciphertext.len() <= config.max_content_len as usize + ReportContent::AEAD_TAG_LEN,
+                IntelMarketError::ContentTooLong
+            );
+            require!(
+                !envelopes.is_empty() && envelopes.len() <= ReportContent::MAX_ENVELOPES,
+                IntelMarketError::InvalidEnvelopes
+            );
+            require!(
+                envelopes
+                    .iter()
+                    .enumerate()
+                    .all(|(i, e)| envelopes[..i].iter().all(|o| o.recipient != e.recipient)),
+                IntelMarketError::InvalidEnvelopes
+            );
+        }
+    }
+    require!(
+        tags.len() <= IntelReport::MAX_TAGS,
+        IntelMarketError::TooManyTags
+    );
+    require!(
+        tags.iter()
+            .enumerate()
+            .all(|(i, tag)| !tags[..i].contains(tag)),
+        IntelMarketError::DuplicateTag
+    );
+    require!(
+        (-IntelReport::MAX_LATITUDE..=IntelReport::MAX_LATITUDE).contains(&latitude),
+        IntelMarketError::LatitudeOutOfRange
+    );
+    require!(
+        (-IntelReport::MAX_LONGITUDE..=IntelReport::MAX_LONGITUDE).contains(&longitude),
+        IntelMarketError::LongitudeOutOfRange
+    );
+    require!(
+        precision.is_on_grid(latitude) && precision.is_on_grid(longitude),
+        IntelMarketError::CoordinatesNotOnGrid
+    );
+    // Backstop for the account size limit that MAX_CONTENT_LEN_LIMIT is derived from, for
+    // updates that grow the report (new reports are checked by allocation_space)
+    require!(
+        IntelReport::space(title, content, tags) <= MAX_PERMITTED_DATA_INCREASE,
+        IntelMarketError::ContentTooLong
+    );
+    Ok(())
+}
+
+// Encrypted bodies are only meaningful for team-restricted reports. Envelopes are not
+// checked against the membership list here; members added later need a new revision.
+pub fn require_encryption_scope(content: &ReportContent, team: Option<Pubkey>) -> Result<()> {
+    if matches!(content, ReportContent::Encrypted { .. }) {
+        require!(
+            team.is_some(),
+            IntelMarketError::EncryptedContentRequiresTeam
+        );
+    }
+    Ok(())
+}
+
+// Observations cannot be reported from the future, beyond a small allowance for clock drift
+pub fn validate_observed_at(observed_at: i64, now: i64) -> Result<()> {
+    require!(
+        observed_at <= now + IntelReport::MAX_CLOCK_SKEW_SECS,
+        IntelMarketError::ObservedInFuture
+    );
+    Ok(())
+}
+
+// Team-restricted reports may only be written by members of that team who vote, so
+// observers can read the team's reports but not publish them. The membership account is
+// program-owned, so matching its fields proves it is the team's PDA.
+pub fn require_team_member(
+    team: Option<Pubkey>,
+    membership: Option<&Account<TeamMembership>>,
+    author: &Pubkey,
+) -> Result<()> {
+    if let Some(team) = team {
+        let membership = membership.ok_or(IntelMarketError::NotTeamMember)?;
+        require!(
+            membership.team == team && membership.member == *author,
+            IntelMarketError::NotTeamMember
+        );
+        require!(membership.role.votes(), IntelMarketError::Unauthorized);
+    }
+    Ok(())
+}
+
+// Optional per-cell index, seeds = ["geo_cell", geohash prefix]. Overlays read the cells
+// covering the viewport to find populated areas, then fetch the reports in them with a
+// memcmp filter on IntelReport::GEOHASH_OFFSET. Reports created with their cell's account
+// are counted in it, and must pass it again when they move out of the cell or are retracted.
+#[account]
+pub struct GeoCell {
+    pub cell: [u8; 4], // Geohash prefix, ~39km x 20km
+    pub report_count: u64,
+    pub bump: u8,
+}
+
+impl GeoCell {
+    pub const SEED: &'static [u8] = b"geo_cell";
+    pub const PRECISION: usize = 4;
+    pub const SPACE: usize = 8 + Self::PRECISION + 8 + 1;
+
+    pub fn cell_of(geohash: &[u8; IntelReport::GEOHASH_LEN]) -> [u8; Self::PRECISION] {
+        let mut cell = [0u8; Self::PRECISION];
+        cell.copy_from_slice(&geohash[..Self::PRECISION]);
+        cell
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(cell: [u8; 4])]
+pub struct InitializeGeoCell<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = GeoCell::SPACE,
+        seeds = [GeoCell::SEED, cell.as_ref()],
+        bump
+    )]
+    pub geo_cell: Account<'info, GeoCell>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Anyone may open the index for a cell
+pub fn initialize_geo_cell(ctx: Context<InitializeGeoCell>, cell: [u8; 4]) -> Result<()> {
+    require!(
+        cell.iter().all(|c| GEOHASH_BASE32.contains(c)),
+        IntelMarketError::InvalidGeoCell
+    );
+    let geo_cell = &mut ctx.accounts.geo_cell;
+    geo_cell.cell = cell;
+    geo_cell.report_count = 0;
+    geo_cell.bump = ctx.bumps.geo_cell;
+    Ok(())
+}
+
+// Counts a report in its cell if the cell account was supplied; returns whether it was indexed
+pub fn index_in_geo_cell(
+    geo_cell: Option<&mut Account<GeoCell>>,
+    geohash: &[u8; IntelReport::GEOHASH_LEN],
+) -> Result<bool> {
+    let Some(geo_cell) = geo_cell else {
+        return Ok(false);
+    };
+    require!(
+        geo_cell.cell == GeoCell::cell_of(geohash),
+        IntelMarketError::GeoCellMismatch
+    );
+    geo_cell.report_count += 1;
+    Ok(true)
+}
+
+// Removes an indexed report from its cell; the cell account is required once indexed
+pub fn unindex_from_geo_cell(
+    geo_cell: Option<&mut Account<GeoCell>>,
+    geohash: &[u8; IntelReport::GEOHASH_LEN],
+) -> Result<()> {
+    let geo_cell = geo_cell.ok_or(IntelMarketError::GeoCellMismatch)?;
+    require!(
+        geo_cell.cell == GeoCell::cell_of(geohash),
+        IntelMarketError::GeoCellMismatch
+    );
+    geo_cell.report_count = geo_cell.report_count.saturating_sub(1);
+    Ok(())
+}
+
+// Tag registry: tags are registered once under a canonical name and reports refer to
+// them by ID, so "SIGINT", "sigint" and "Sig-Int" are the same tag. Each (tag, report)
+// pair can be indexed with a TagEntry so clients can list a tag's reports with a single
+// memcmp query instead of scanning every report.
+
+// Registry PDA, seeds = ["tag_registry"]. `tag_count` is the ID the next tag will get.
+#[account]
+pub struct TagRegistry {
+    pub tag_count: u16,
+    pub bump: u8,
+}
+
+impl TagRegistry {
+    pub const SEED: &'static [u8] = b"tag_registry";
+    pub const SPACE: usize = 8 + 2 + 1;
+
+    pub fn require_registered(&self, tags: &[u16]) -> Result<()> {
+        require!(
+            tags.iter().all(|tag| *tag < self.tag_count),
+            IntelMarketError::UnknownTag
+        );
+        Ok(())
+    }
+}
+
+// Tag PDA, seeds = ["tag", id (u16 LE)]. Clients list all categories by fetching every Tag.
+#[account]
+pub struct Tag {
+    pub id: u16,
+    pub name: String,      // Canonical name, see canonical_tag_name
+    pub report_count: u64, // Live TagEntry accounts for this tag
+    pub created_by: Pubkey,
+    pub bump: u8,
+}
+
+impl Tag {
+    pub const SEED: &'static [u8] = b"tag";
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const SPACE: usize = 8 + 2 + (4 + Self::MAX_NAME_LEN) + 8 + 32 + 1;
+
+    // Registered tags among `accounts`, usually an instruction's remaining accounts.
+    // Anything that is not a Tag PDA is ignored.
+    pub fn load_all(accounts: &[AccountInfo]) -> Result<Vec<Tag>> {
+        let mut tags = Vec::new();
+        for info in accounts {
+            if info.owner != &crate::ID {
+                continue;
+            }
+            let Ok(tag) = Self::try_deserialize(&mut &info.try_borrow_data()?[..]) else {
+                continue;
+            };
+            let address = Pubkey::create_program_address(
+                &[Self::SEED, &tag.id.to_le_bytes(), &[tag.bump]],
+                &crate::ID,
+            );
+            if address.as_ref() == Ok(info.key) {
+                tags.push(tag);
+            }
+        }
+        Ok(tags)
+    }
+}
+
+// Name lookup PDA, seeds = ["tag_name", canonical name]. Its uniqueness keeps one ID per name.
+#[account]
+pub struct TagName {
+    pub tag: u16,
+    pub bump: u8,
+}
+
+impl TagName {
+    pub const SEED: &'static [u8] = b"tag_name";
+    pub const SPACE: usize = 8 + 2 + 1;
+}
+
+// Tag index PDA, seeds = ["tag_entry", tag (u16 LE), report]
+#[account]
+pub struct TagEntry {
+    pub tag: u16,
+    pub report: Pubkey,
+    pub payer: Pubkey, // Receives the rent back when the entry is removed
+    pub bump: u8,
+}
+
+impl TagEntry {
+    pub const SEED: &'static [u8] = b"tag_entry";
+    pub const SPACE: usize = 8 + 2 + 32 + 32 + 1;
+    pub const TAG_OFFSET: usize = 8;
+}
+
+// Upper-cased ASCII letters and digits only; separators, spaces and anything else are dropped
+pub fn canonical_tag_name(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[derive(Accounts)]
+pub struct InitializeTagRegistry<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = TagRegistry::SPACE,
+        seeds = [TagRegistry::SEED],
+        bump
+    )]
+    pub tag_registry: Account<'info, TagRegistry>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_tag_registry(ctx: Context<InitializeTagRegistry>) -> Result<()> {
+    let registry = &mut ctx.accounts.tag_registry;
+    registry.tag_count = 0;
+    registry.bump = ctx.bumps.tag_registry;
+    Ok(())
+}
+
+// Only the config admin registers tags, since the u16 ID space and canonical names are
+// shared by every author; registering a name that is already taken in any spelling fails
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterTag<'info> {
+    #[account(
+        has_one = admin @ IntelMarketError::Unauthorized,
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [TagRegistry::SEED], bump = tag_registry.bump)]
+    pub tag_registry: Account<'info, TagRegistry>,
+    #[account(
+        init,
+        payer = admin,
+        space = Tag::SPACE,
+        seeds = [Tag::SEED, &tag_registry.tag_count.to_le_bytes()],
+        bump
+    )]
+    pub tag: Account<'info, Tag>,
+    #[account(
+        init,
+        payer = admin,
+        space = TagName::SPACE,
+        seeds = [TagName::SEED, canonical_tag_name(&name).as_bytes()],
+        bump
+    )]
+    pub tag_name: Account<'info, TagName>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn register_tag(ctx: Context<RegisterTag>, name: String) -> Result<()> {
+    let name = canonical_tag_name(&name);
+    require!(!name.is_empty(), IntelMarketError::EmptyTag);
+    require!(
+        name.len() <= Tag::MAX_NAME_LEN,
+        IntelMarketError::TagTooLong
+    );
+    let registry = &mut ctx.accounts.tag_registry;
+    let id = registry.tag_count;
+    registry.tag_count = id.checked_add(1).ok_or(IntelMarketError::TagRegistryFull)?;
+
+    let tag = &mut ctx.accounts.tag;
+    tag.id = id;
+    tag.name = name;
+    tag.report_count = 0;
+    tag.created_by = ctx.accounts.admin.key();
+    tag.bump = ctx.bumps.tag;
+    ctx.accounts.tag_name.tag = id;
+    ctx.accounts.tag_name.bump = ctx.bumps.tag_name;
+
+    emit!(TagRegistered {
+        tag: id,
+        name: tag.name.clone(),
+    });
+    Ok(())
+}
+
+// The author indexes each of a report's tags after creating it, one tag per instruction
+#[derive(Accounts)]
+pub struct IndexReportTag<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [Tag::SEED, &tag.id.to_le_bytes()], bump = tag.bump)]
+    pub tag: Account<'info, Tag>,
+    #[account(
+        has_one = author,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    #[account(
+        init,
+        payer = author,
+        space = TagEntry::SPACE,
+        seeds = [TagEntry::SEED, &tag.id.to_le_bytes(), intel_report.key().as_ref()],
+        bump
+    )]
+    pub tag_entry: Account<'info, TagEntry>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn index_report_tag(ctx: Context<IndexReportTag>) -> Result<()> {
+    let tag = &mut ctx.accounts.tag;
+    let report = &ctx.accounts.intel_report;
+    require!(
+        report.tags.contains(&tag.id),
+        IntelMarketError::TagNotOnReport
+    );
+    tag.report_count += 1;
+
+    let entry = &mut ctx.accounts.tag_entry;
+    entry.tag = tag.id;
+    entry.report = report.key();
+    entry.payer = report.author;
+    entry.bump = ctx.bumps.tag_entry;
+
+    emit!(ReportTagged {
+        tag: tag.id,
+        report: entry.report,
+    });
+    Ok(())
+}
+
+// Anyone may remove an entry once the report has been retracted or no longer carries the tag
 #[derive(Accounts)]
+pub struct UnindexReportTag<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut, seeds = [Tag::SEED, &tag.id.to_le_bytes()], bump = tag.bump)]
+    pub tag: Account<'info, Tag>,
+    /// CHECK: the indexed report; may already be closed
+    #[account(address = tag_entry.report)]
+    pub intel_report: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [TagEntry::SEED, &tag.id.to_le_bytes(), tag_entry.report.as_ref()],
+        bump = tag_entry.bump
+    )]
+    pub tag_entry: Account<'info, TagEntry>,
+    /// CHECK: rent refund destination, must match the entry's payer
+    #[account(mut, address = tag_entry.payer)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+pub fn unindex_report_tag(ctx: Context<UnindexReportTag>) -> Result<()> {
+    let tag = &mut ctx.accounts.tag;
+    let report = &ctx.accounts.intel_report;
+    if report.owner == &crate::ID && !report.data_is_empty() {
+        // Only current reports carry registered tags
+        if let ReportLayout::Current(report) =
+            IntelReport::load_versioned(&report.try_borrow_data()?)?
+        {
+            require!(
+                !report.tags.contains(&tag.id),
+                IntelMarketError::TagStillOnReport
+            );
+        }
+    }
+    tag.report_count = tag.report_count.saturating_sub(1);
+
+    emit!(ReportUntagged {
+        tag: tag.id,
+        report: ctx.accounts.tag_entry.report,
+    });
+    Ok(())
+}
+
+// Grow or shrink a program-owned account to `new_space`, topping up rent from
+// `payer` or refunding the surplus to it.
+pub fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_space: usize,
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(new_space);
+    let current = account.lamports();
+    if required > current {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            required - current,
+        )?;
+    } else if current > required {
+        **account.try_borrow_mut_lamports()? -= current - required;
+        **payer.to_account_info().try_borrow_mut_lamports()? += current - required;
+    }
+    account.realloc(new_space, false)?;
+    Ok(())
+}
+
+// The caller passes the sequence it expects to use; a retried submission that already
+// landed fails with SequenceMismatch instead of creating a duplicate report.
+#[derive(Accounts)]
+#[instruction(sequence: u64, title: String, content: ReportContent, tags: Vec<u16>)]
 pub struct CreateIntelReport<'info> {
-    #[account(init, payer = author, space = 8 + 256 + 1024 + 64 + 8 + 8 + 8 + 32)]
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = author,
+        space = IntelReport::allocation_space(&title, &content, &tags)?,
+        seeds = [IntelReport::SEED, author.key().as_ref(), &sequence.to_le_bytes()],
+        bump
+    )]
     pub intel_report: Account<'info, IntelReport>,
+    #[account(
+        mut,
+        seeds = [AuthorState::SEED, author.key().as_ref()],
+        bump = author_state.bump,
+        constraint = author_state.report_count == sequence @ IntelMarketError::SequenceMismatch
+    )]
+    pub author_state: Account<'info, AuthorState>,
+    #[account(seeds = [TagRegistry::SEED], bump = tag_registry.bump)]
+    pub tag_registry: Account<'info, TagRegistry>,
+    // Required when the report is restricted to a team: the author's membership in it
+    pub team_membership: Option<Account<'info, TeamMembership>>,
+    // Index the report in its GeoCell when supplied
+    #[account(mut)]
+    pub geo_cell: Option<Account<'info, GeoCell>>,
+    /// CHECK: fee destination, checked against the config when a flat fee is due
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
     #[account(mut)]
     pub author: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_intel_report(
     ctx: Context<CreateIntelReport>,
+    sequence: u64,
     title: String,
-    content: String,
-    tags: Vec<String>,
-    latitude: f64,
-    longitude: f64,
-    timestamp: i64,
+    content: ReportContent,
+    tags: Vec<u16>,
+    latitude: i32,
+    longitude: i32,
+    precision: LocationPrecision,
+    observed_at: i64,
+    classification: Classification,
+    team: Option<Pubkey>,
 ) -> Result<()> {
+    validate_report_fields(
+        &ctx.accounts.config,
+        &title,
+        &content,
+        &tags,
+        latitude,
+        longitude,
+        precision,
+    )?;
+    require_encryption_scope(&content, team)?;
+    ctx.accounts.tag_registry.require_registered(&tags)?;
+    let now = Clock::get()?.unix_timestamp;
+    validate_observed_at(observed_at, now)?;
+    require_team_member(
+        team,
+        ctx.accounts.team_membership.as_ref(),
+        ctx.accounts.author.key,
+    )?;
     let report = &mut ctx.accounts.intel_report;
+    report.latitude = latitude;
+    report.longitude = longitude;
+    report.location_precision = precision;
+    report.version = IntelReport::VERSION;
+    report.geohash = geohash_encode(report.latitude, report.longitude);
+    report.geo_indexed = index_in_geo_cell(ctx.accounts.geo_cell.as_mut(), &report.geohash)?;
     report.title = title;
     report.content = content;
     report.tags = tags;
+    report.observed_at = observed_at;
+    report.created_at = now;
+    report.author = *ctx.accounts.author.key;
+    report.revision = 1;
+    report.previous = None;
+    report.superseded_by = None;
+    report.sequence = sequence;
+    report.bump = ctx.bumps.intel_report;
+    report.classification = classification;
+    report.team = team;
+    ctx.accounts.author_state.report_count += 1;
+    ctx.accounts
+        .author_state
+        .record_report(&ctx.accounts.config, now)?;
+    charge_flat_fee(
+        &ctx.accounts.config,
+        ctx.accounts.config.fees.report_fee,
+        &ctx.accounts.author,
+        ctx.accounts.fee_recipient.as_deref(),
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(IntelReportCreated {
+        report: report.key(),
+        author: report.author,
+        revision: report.revision,
+        previous: None,
+        classification: report.classification,
+        team: report.team,
+        latitude: report.latitude,
+        longitude: report.longitude,
+        geohash: report.geohash,
+        observed_at: report.observed_at,
+        created_at: report.created_at,
+    });
+    Ok(())
+}
+
+// Anchor instruction for correcting an Intel Report in place (author only).
+// Fields passed as None are left unchanged; superseded reports are frozen.
+// The account is resized to fit the new contents, with the author covering any rent difference.
+// An indexed report that moves to another GeoCell must pass both its old and new cells.
+// Listed reports are frozen so buyers get what they paid for.
+#[derive(Accounts)]
+pub struct UpdateIntelReport<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        has_one = author,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    /// CHECK: the report's listing PDA, which must not exist
+    #[account(
+        seeds = [Listing::SEED, intel_report.key().as_ref()],
+        bump,
+        constraint = listing.data_is_empty() @ IntelMarketError::ReportListed
+    )]
+    pub listing: UncheckedAccount<'info>,
+    // Required when the tags change
+    #[account(seeds = [TagRegistry::SEED], bump = tag_registry.bump)]
+    pub tag_registry: Option<Account<'info, TagRegistry>>,
+    #[account(mut)]
+    pub previous_geo_cell: Option<Account<'info, GeoCell>>,
+    #[account(mut)]
+    pub geo_cell: Option<Account<'info, GeoCell>>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_intel_report(
+    ctx: Context<UpdateIntelReport>,
+    title: Option<String>,
+    content: Option<ReportContent>,
+    tags: Option<Vec<u16>>,
+    latitude: Option<i32>,
+    longitude: Option<i32>,
+    precision: Option<LocationPrecision>,
+    classification: Option<Classification>,
+) -> Result<()> {
+    let report = &mut ctx.accounts.intel_report;
+    require!(
+        report.superseded_by.is_none(),
+        IntelMarketError::ReportSuperseded
+    );
+    if let Some(title) = title {
+        report.title = title;
+    }
+    if let Some(content) = content {
+        report.content = content;
+    }
+    if let Some(tags) = tags {
+        ctx.accounts
+            .tag_registry
+            .as_ref()
+            .ok_or(IntelMarketError::UnknownTag)?
+            .require_registered(&tags)?;
+        report.tags = tags;
+    }
+    if let Some(latitude) = latitude {
+        report.latitude = latitude;
+    }
+    if let Some(longitude) = longitude {
+        report.longitude = longitude;
+    }
+    if let Some(precision) = precision {
+        report.location_precision = precision;
+    }
+    if let Some(classification) = classification {
+        report.classification = classification;
+    }
+    validate_report_fields(
+        &ctx.accounts.config,
+        &report.title,
+        &report.content,
+        &report.tags,
+        report.latitude,
+        report.longitude,
+        report.location_precision,
+    )?;
+    require_encryption_scope(&report.content, report.team)?;
+    let geohash = geohash_encode(report.latitude, report.longitude);
+    if report.geo_indexed && GeoCell::cell_of(&geohash) != GeoCell::cell_of(&report.geohash) {
+        unindex_from_geo_cell(ctx.accounts.previous_geo_cell.as_mut(), &report.geohash)?;
+        require!(
+            index_in_geo_cell(ctx.accounts.geo_cell.as_mut(), &geohash)?,
+            IntelMarketError::GeoCellMismatch
+        );
+    }
+    report.geohash = geohash;
+
+    let new_space = IntelReport::space(&report.title, &report.content, &report.tags);
+    resize_account(
+        &report.to_account_info(),
+        &ctx.accounts.author,
+        &ctx.accounts.system_program,
+        new_space,
+    )?;
+
+    emit!(IntelReportUpdated {
+        report: report.key(),
+        author: report.author,
+        revision: report.revision,
+        latitude: report.latitude,
+        longitude: report.longitude,
+        geohash: report.geohash,
+        updated_at: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Anchor instruction for replacing an Intel Report with a new revision.
+// The old account is kept and linked both ways so the history stays on-chain.
+// The new revision takes the author's next sequence number, like create_intel_report,
+// and stays restricted to the previous revision's team.
+#[derive(Accounts)]
+#[instruction(sequence: u64, title: String, content: ReportContent, tags: Vec<u16>)]
+pub struct SupersedeIntelReport<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        has_one = author,
+        constraint = previous_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub previous_report: Account<'info, IntelReport>,
+    #[account(
+        init,
+        payer = author,
+        space = IntelReport::allocation_space(&title, &content, &tags)?,
+        seeds = [IntelReport::SEED, author.key().as_ref(), &sequence.to_le_bytes()],
+        bump
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    #[account(
+        mut,
+        seeds = [AuthorState::SEED, author.key().as_ref()],
+        bump = author_state.bump,
+        constraint = author_state.report_count == sequence @ IntelMarketError::SequenceMismatch
+    )]
+    pub author_state: Account<'info, AuthorState>,
+    #[account(seeds = [TagRegistry::SEED], bump = tag_registry.bump)]
+    pub tag_registry: Account<'info, TagRegistry>,
+    // Required when the previous revision is restricted to a team
+    pub team_membership: Option<Account<'info, TeamMembership>>,
+    // Index the new revision in its GeoCell when supplied
+    #[account(mut)]
+    pub geo_cell: Option<Account<'info, GeoCell>>,
+    /// CHECK: fee destination, checked against the config when a flat fee is due
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn supersede_intel_report(
+    ctx: Context<SupersedeIntelReport>,
+    sequence: u64,
+    title: String,
+    content: ReportContent,
+    tags: Vec<u16>,
+    latitude: i32,
+    longitude: i32,
+    precision: LocationPrecision,
+    observed_at: i64,
+    classification: Classification,
+) -> Result<()> {
+    validate_report_fields(
+        &ctx.accounts.config,
+        &title,
+        &content,
+        &tags,
+        latitude,
+        longitude,
+        precision,
+    )?;
+    ctx.accounts.tag_registry.require_registered(&tags)?;
+    let now = Clock::get()?.unix_timestamp;
+    validate_observed_at(observed_at, now)?;
+    let previous = &mut ctx.accounts.previous_report;
+    require!(
+        previous.superseded_by.is_none(),
+        IntelMarketError::ReportSuperseded
+    );
+    require_team_member(
+        previous.team,
+        ctx.accounts.team_membership.as_ref(),
+        ctx.accounts.author.key,
+    )?;
+    require_encryption_scope(&content, previous.team)?;
+    previous.superseded_by = Some(ctx.accounts.intel_report.key());
+
+    let report = &mut ctx.accounts.intel_report;
     report.latitude = latitude;
     report.longitude = longitude;
-    report.timestamp = timestamp;
+    report.location_precision = precision;
+    report.version = IntelReport::VERSION;
+    report.geohash = geohash_encode(report.latitude, report.longitude);
+    report.geo_indexed = index_in_geo_cell(ctx.accounts.geo_cell.as_mut(), &report.geohash)?;
+    report.title = title;
+    report.content = content;
+    report.tags = tags;
+    report.observed_at = observed_at;
+    report.created_at = now;
     report.author = *ctx.accounts.author.key;
+    report.revision = previous.revision + 1;
+    report.previous = Some(previous.key());
+    report.superseded_by = None;
+    report.sequence = sequence;
+    report.bump = ctx.bumps.intel_report;
+    report.classification = classification;
+    report.team = previous.team;
+    ctx.accounts.author_state.report_count += 1;
+    ctx.accounts
+        .author_state
+        .record_report(&ctx.accounts.config, now)?;
+    charge_flat_fee(
+        &ctx.accounts.config,
+        ctx.accounts.config.fees.report_fee,
+        &ctx.accounts.author,
+        ctx.accounts.fee_recipient.as_deref(),
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(IntelReportCreated {
+        report: report.key(),
+        author: report.author,
+        revision: report.revision,
+        previous: report.previous,
+        classification: report.classification,
+        team: report.team,
+        latitude: report.latitude,
+        longitude: report.longitude,
+        geohash: report.geohash,
+        observed_at: report.observed_at,
+        created_at: report.created_at,
+    });
     Ok(())
 }
 
-// Anchor instruction for creating Intel Reports
+// Anchor instruction for retracting an Intel Report (author only).
+// Closes the account, returns its rent to the author and emits a tombstone for indexers.
+// Superseded revisions stay on-chain as history; retract the latest revision instead.
+// A listed report must be delisted first, which refunds any pending buyer.
+#[derive(Accounts)]
+pub struct RetractIntelReport<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        has_one = author,
+        close = author,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    /// CHECK: the report's listing PDA, which must not exist
+    #[account(
+        seeds = [Listing::SEED, intel_report.key().as_ref()],
+        bump,
+        constraint = listing.data_is_empty() @ IntelMarketError::ReportListed
+    )]
+    pub listing: UncheckedAccount<'info>,
+    // Required when the report is indexed in a GeoCell
+    #[account(mut)]
+    pub geo_cell: Option<Account<'info, GeoCell>>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+}
+
+pub fn retract_intel_report(ctx: Context<RetractIntelReport>) -> Result<()> {
+    let report = &ctx.accounts.intel_report;
+    require!(
+        report.superseded_by.is_none(),
+        IntelMarketError::ReportSuperseded
+    );
+    if report.geo_indexed {
+        unindex_from_geo_cell(ctx.accounts.geo_cell.as_mut(), &report.geohash)?;
+    }
+    emit!(IntelReportRetracted {
+        report: report.key(),
+        author: report.author,
+        revision: report.revision,
+        retracted_at: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
 
 // Anchor instruction for fetching all Intel Reports (for overlays/UI)
 // NOTE: Anchor does not support on-chain account enumeration directly; fetching is done off-chain using account filters.
@@ -53,57 +1286,4527 @@ pub fn create_intel_report(
 // Example (off-chain, using Anchor client or @solana/web3.js):
 //   const reports = await program.account.intelReport.all();
 //   // Filter by tags, location, etc. as needed for overlays
+// Reports with a given tag are listed through its index entries:
+//   const entries = await program.account.tagEntry.all([
+//     { memcmp: { offset: 8, bytes: bs58.encode(new BN(tagId).toArrayLike(Buffer, "le", 2)) } }, // TagEntry::TAG_OFFSET
+//   ]);
+// Reports inside a viewport are fetched by geohash prefix, one query per covering cell:
+//   const reports = await program.account.intelReport.all([
+//     { memcmp: { offset: 9, bytes: bs58.encode(Buffer.from("9q8y")) } }, // IntelReport::GEOHASH_OFFSET
+//   ]);
+// A single report is fetched directly from its PDA ("report N by author X"):
+//   const [pda] = PublicKey.findProgramAddressSync(
+//     [Buffer.from("intel_report"), author.toBuffer(), new BN(n).toArrayLike(Buffer, "le", 8)],
+//     program.programId
+//   );
+// After the initial load, subscribe to events instead of re-polling:
+//   program.addEventListener("intelReportCreated", (event) => { /* add overlay */ });
+//   program.addEventListener("intelReportRetracted", (event) => { /* drop overlay */ });
 
 // No on-chain instruction needed for listing; all fetching is done off-chain for overlays and UI.
 // All changes must be documented in overlays and integration artifacts.
 
 // CyberTeam account schema for private team collaboration
+// Members are stored as one TeamMembership PDA per (team, member), so team size is unbounded.
 #[account]
 pub struct CyberTeam {
-    pub name: String,         // Case room name
-    pub members: Vec<Pubkey>, // Wallet addresses of team members
+    pub version: u8,         // Layout version, CyberTeam::VERSION for current accounts
+    pub authority: Pubkey,   // Team owner (always an admin, cannot be removed)
+    pub name: String,        // Case room name
+    pub member_count: u32,   // Number of live TeamMembership accounts
+    pub voter_count: u32,    // Members who vote on proposals (admins and analysts)
+    pub open: bool,          // Whether any wallet may join as an observer
+    pub threshold: u8,       // Approvals needed for a proposal; above 1, admins cannot act alone
+    pub proposal_count: u64, // Seeds the next TeamProposal PDA
 }
 
-#[derive(Accounts)]
-pub struct CreateCyberTeam<'info> {
-    #[account(init, payer = authority, space = 8 + 4 + 256 + (4 + 32 * 9))]
-    pub cyber_team: Account<'info, CyberTeam>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
+impl CyberTeam {
+    pub const MAX_NAME_LEN: usize = 256;
+    pub const SPACE: usize = 8 + 1 + 32 + (4 + Self::MAX_NAME_LEN) + 4 + 4 + 1 + 1 + 8;
+    pub const VERSION: u8 = 2;
+
+    // Single-admin membership changes are only allowed while no multisig is configured
+    pub fn require_single_signer(&self) -> Result<()> {
+        require!(self.threshold <= 1, IntelMarketError::MultisigRequired);
+        Ok(())
+    }
+
+    // Keeps voter_count in step when a member joins (`from` is None), leaves (`to` is None)
+    // or changes role. A change that would leave fewer voters than the threshold is refused,
+    // since the team could then never approve another proposal.
+    pub fn record_role_change(
+        &mut self,
+        from: Option<TeamRole>,
+        to: Option<TeamRole>,
+    ) -> Result<()> {
+        let voted = from.is_some_and(TeamRole::votes);
+        let votes = to.is_some_and(TeamRole::votes);
+        if voted && !votes {
+            require!(
+                self.voter_count > self.threshold.max(1) as u32,
+                IntelMarketError::ThresholdExceedsVoters
+            );
+            self.voter_count -= 1;
+        } else if votes && !voted {
+            self.voter_count += 1;
+        }
+        Ok(())
+    }
 }
 
-pub fn create_cyber_team(ctx: Context<CreateCyberTeam>, name: String) -> Result<()> {
-    let team = &mut ctx.accounts.cyber_team;
-    team.name = name;
-    team.members = vec![*ctx.accounts.authority.key];
-    Ok(())
+// Member roles: admins manage membership, analysts contribute, observers read only
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TeamRole {
+    Admin,
+    Analyst,
+    Observer,
 }
 
-#[derive(Accounts)]
-pub struct AddMember<'info> {
-    #[account(mut, has_one = authority)]
-    pub cyber_team: Account<'info, CyberTeam>,
-    pub authority: Signer<'info>,
+impl TeamRole {
+    // Observers may join teams freely, so only admins and analysts vote
+    pub fn votes(self) -> bool {
+        self != TeamRole::Observer
+    }
 }
 
-pub fn add_member(ctx: Context<AddMember>, new_member: Pubkey) -> Result<()> {
-    let team = &mut ctx.accounts.cyber_team;
-    if !team.members.contains(&new_member) {
-        team.members.push(new_member);
+// Membership PDA, seeds = ["membership", team, member]
+#[account]
+pub struct TeamMembership {
+    pub team: Pubkey,   // CyberTeam this membership belongs to
+    pub member: Pubkey, // Member wallet address
+    pub role: TeamRole, // Member role within the team
+    pub payer: Pubkey,  // Receives the rent back when the membership is closed
+    pub joined_at: i64, // Unix timestamp (on-chain clock)
+    pub bump: u8,
+}
+
+impl TeamMembership {
+    pub const SEED: &'static [u8] = b"membership";
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 32 + 8 + 1;
+    // Byte offset of `team`, for memcmp filters when enumerating members
+    pub const TEAM_OFFSET: usize = 8;
+    // Byte offset of `member`, for memcmp filters when enumerating a wallet's teams
+    pub const MEMBER_OFFSET: usize = 8 + 32;
+
+    // Distinct memberships of `team` among `accounts`, usually an instruction's remaining
+    // accounts. Anything that is not a live membership PDA of the team is ignored.
+    pub fn load_all(team: &Pubkey, accounts: &[AccountInfo]) -> Result<Vec<TeamMembership>> {
+        let mut memberships: Vec<TeamMembership> = Vec::new();
+        for info in accounts {
+            if info.owner != &crate::ID {
+                continue;
+            }
+            let Ok(membership) = Self::try_deserialize(&mut &info.try_borrow_data()?[..]) else {
+                continue;
+            };
+            let address = Pubkey::create_program_address(
+                &[
+                    Self::SEED,
+                    team.as_ref(),
+                    membership.member.as_ref(),
+                    &[membership.bump],
+                ],
+                &crate::ID,
+            );
+            if membership.team == *team
+                && address.as_ref() == Ok(info.key)
+                && !memberships.iter().any(|m| m.member == membership.member)
+            {
+                memberships.push(membership);
+            }
+        }
+        Ok(memberships)
     }
-    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct RemoveMember<'info> {
-    #[account(mut, has_one = authority)]
-    pub cyber_team: Account<'info, CyberTeam>,
-    pub authority: Signer<'info>,
+// The owner manages everyone; other admins may only manage analysts and observers,
+// so a delegated team lead cannot promote peers or lock out other admins.
+pub fn can_manage(team: &CyberTeam, admin: &TeamMembership, target_role: TeamRole) -> bool {
+    if admin.member == team.authority {
+        return true;
+    }
+    admin.role == TeamRole::Admin && target_role != TeamRole::Admin
 }
 
-pub fn remove_member(ctx: Context<RemoveMember>, member: Pubkey) -> Result<()> {
-    let team = &mut ctx.accounts.cyber_team;
-    team.members.retain(|m| m != &member);
-    Ok(())
+// Whether `actor` could make a proposal's change directly. While the threshold is 1 a
+// proposal executes on a single approval, so that approver must pass the same checks as
+// the direct instructions. `target_role` is the current role of the member a RemoveMember
+// or ChangeRole proposal targets.
+pub fn can_act_alone(
+    team: &CyberTeam,
+    actor: &TeamMembership,
+    action: &ProposalAction,
+    target_role: Option<TeamRole>,
+) -> bool {
+    match action {
+        ProposalAction::AddMember { role, .. } => can_manage(team, actor, *role),
+        ProposalAction::RemoveMember { .. } => {
+            target_role.is_some_and(|current| can_manage(team, actor, current))
+        }
+        ProposalAction::ChangeRole { role, .. } => target_role.is_some_and(|current| {
+            can_manage(team, actor, current) && can_manage(team, actor, *role)
+        }),
+        ProposalAction::Rename { .. } | ProposalAction::SetThreshold { .. } => {
+            actor.role == TeamRole::Admin
+        }
+    }
+}
+
+// Events for every IntelReport and CyberTeam state change, so indexers and the
+// relay node can stream changes instead of scanning all program accounts.
+#[event]
+pub struct IntelReportCreated {
+    pub report: Pubkey,
+    pub author: Pubkey,
+    pub revision: u32,
+    pub previous: Option<Pubkey>, // Set when the report supersedes an older revision
+    pub classification: Classification,
+    pub team: Option<Pubkey>,
+    pub latitude: i32,
+    pub longitude: i32,
+    pub geohash: [u8; 8],
+    pub observed_at: i64,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct IntelReportUpdated {
+    pub report: Pubkey,
+    pub author: Pubkey,
+    pub revision: u32,
+    pub latitude: i32,
+    pub longitude: i32,
+    pub geohash: [u8; 8],
+    pub updated_at: i64,
+}
+
+// Tombstone event: off-chain indexers drop the report from overlays when they see this
+#[event]
+pub struct IntelReportRetracted {
+    pub report: Pubkey,
+    pub author: Pubkey,
+    pub revision: u32,
+    pub retracted_at: i64,
+}
+
+#[event]
+pub struct TagRegistered {
+    pub tag: u16,
+    pub name: String,
+}
+
+#[event]
+pub struct ReportTagged {
+    pub tag: u16,
+    pub report: Pubkey,
+}
+
+#[event]
+pub struct ReportUntagged {
+    pub tag: u16,
+    pub report: Pubkey,
+}
+
+#[event]
+pub struct CyberTeamCreated {
+    pub team: Pubkey,
+    pub authority: Pubkey,
+    pub name: String,
+    pub open: bool,
+}
+
+#[event]
+pub struct TeamMemberAdded {
+    pub team: Pubkey,
+    pub member: Pubkey,
+    pub role: TeamRole,
+    pub added_by: Pubkey, // The inviter, or the member itself for self-service joins
+}
+
+#[event]
+pub struct TeamMemberRoleChanged {
+    pub team: Pubkey,
+    pub member: Pubkey,
+    pub role: TeamRole,
+    pub changed_by: Pubkey,
+}
+
+#[event]
+pub struct TeamMemberRemoved {
+    pub team: Pubkey,
+    pub member: Pubkey,
+    pub removed_by: Pubkey, // The member itself when leaving
+}
+
+#[event]
+pub struct TeamInvitationCreated {
+    pub team: Pubkey,
+    pub invitee: Pubkey,
+    pub role: TeamRole,
+    pub inviter: Pubkey,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct TeamInvitationClosed {
+    pub team: Pubkey,
+    pub invitee: Pubkey,
+    pub declined: bool, // False when closed after expiry
+}
+
+#[event]
+pub struct CyberTeamUpdated {
+    pub team: Pubkey,
+    pub name: String,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct TeamProposalCreated {
+    pub team: Pubkey,
+    pub proposal: Pubkey,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct TeamProposalApproved {
+    pub team: Pubkey,
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct TeamProposalExecuted {
+    pub team: Pubkey,
+    pub proposal: Pubkey,
+}
+
+#[event]
+pub struct TeamProposalExpired {
+    pub team: Pubkey,
+    pub proposal: Pubkey,
+}
+
+#[error_code]
+pub enum IntelMarketError {
+    #[msg("Signer is not allowed to manage this team's membership")]
+    Unauthorized,
+    #[msg("The team owner cannot be removed")]
+    CannotRemoveOwner,
+    #[msg("This team does not accept open membership")]
+    TeamClosed,
+    #[msg("This report has been superseded by a newer revision")]
+    ReportSuperseded,
+    #[msg("Report title exceeds the maximum length")]
+    TitleTooLong,
+    #[msg("Report content exceeds the maximum length")]
+    ContentTooLong,
+    #[msg("Off-chain content CID exceeds the maximum length")]
+    CidTooLong,
+    #[msg("Report has too many tags")]
+    TooManyTags,
+    #[msg("Tag name exceeds the maximum length")]
+    TagTooLong,
+    #[msg("Latitude must be between -90 and 90 degrees")]
+    LatitudeOutOfRange,
+    #[msg("Longitude must be between -180 and 180 degrees")]
+    LongitudeOutOfRange,
+    #[msg("Report sequence does not match the author's next sequence number")]
+    SequenceMismatch,
+    #[msg("Author is not a member of the report's team")]
+    NotTeamMember,
+    #[msg("Listing price must be greater than zero")]
+    InvalidPrice,
+    #[msg("Another purchase of this listing is already in escrow")]
+    EscrowOccupied,
+    #[msg("The listing has no pending purchase for this buyer")]
+    NoPendingPurchase,
+    #[msg("The escrow hold period has not elapsed")]
+    EscrowHoldActive,
+    #[msg("The pending buyer and access accounts must be supplied")]
+    BuyerAccountMissing,
+    #[msg("Payment mint does not match the listing")]
+    PaymentMintMismatch,
+    #[msg("Token accounts for the listing's payment mint must be supplied")]
+    TokenAccountsMissing,
+    #[msg("Basis points must not exceed 10,000 and revenue shares must sum to exactly 10,000")]
+    InvalidBasisPoints,
+    #[msg("Listing has too many revenue shares")]
+    TooManyRevenueShares,
+    #[msg("Revenue share accounts do not match the listing's shares")]
+    RevenueShareAccountsMismatch,
+    #[msg("Protocol fee account does not match the config")]
+    FeeAccountMismatch,
+    #[msg("Only buyers with a settled purchase may rate a report")]
+    AccessNotGranted,
+    #[msg("Rating score must be between 1 and 5")]
+    InvalidRating,
+    #[msg("The escrow is frozen by an open dispute")]
+    DisputeActive,
+    #[msg("A dispute has already been opened for this purchase")]
+    DisputeAlreadyOpened,
+    #[msg("Disputes must be opened before the escrow hold period ends")]
+    DisputeWindowClosed,
+    #[msg("The listing has no open dispute")]
+    NoActiveDispute,
+    #[msg("Signer is not a designated arbiter for this dispute")]
+    NotArbiter,
+    #[msg("Too many arbiters")]
+    TooManyArbiters,
+    #[msg("This team requires a multisig proposal for membership changes")]
+    MultisigRequired,
+    #[msg("Team name exceeds the maximum length")]
+    TeamNameTooLong,
+    #[msg("Threshold must be at least 1 and no more than the number of members")]
+    InvalidThreshold,
+    #[msg("Proposal has expired")]
+    ProposalExpired,
+    #[msg("Proposal has not expired yet")]
+    ProposalNotExpired,
+    #[msg("Proposal does not have enough approvals")]
+    ProposalNotApproved,
+    #[msg("Signer has already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Accounts do not match the proposal's action")]
+    ProposalActionMismatch,
+    #[msg("Invitation has expired")]
+    InvitationExpired,
+    #[msg("Only the invitee may close an invitation before it expires")]
+    InvitationNotExpired,
+    #[msg("Geohash cell must be base32 characters")]
+    InvalidGeoCell,
+    #[msg("GeoCell account does not match the report's location")]
+    GeoCellMismatch,
+    #[msg("Tag is not registered")]
+    UnknownTag,
+    #[msg("A report lists the same tag more than once")]
+    DuplicateTag,
+    #[msg("Tag name must contain at least one letter or digit")]
+    EmptyTag,
+    #[msg("The tag registry is full")]
+    TagRegistryFull,
+    #[msg("The report does not carry this tag")]
+    TagNotOnReport,
+    #[msg("The report still carries this tag")]
+    TagStillOnReport,
+    #[msg("Observation time is in the future")]
+    ObservedInFuture,
+    #[msg("Encrypted content needs 1 to 32 key envelopes with distinct recipients")]
+    InvalidEnvelopes,
+    #[msg("Encrypted content is only allowed on team-restricted reports")]
+    EncryptedContentRequiresTeam,
+    #[msg("Account layout version is not supported")]
+    UnsupportedAccountVersion,
+    #[msg("Account already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("The program is paused")]
+    ProgramPaused,
+    #[msg("Author must wait for the minimum interval between reports")]
+    ReportRateLimited,
+    #[msg("Author has reached the daily report cap")]
+    DailyReportCapReached,
+    #[msg("Evidence media type exceeds the maximum length")]
+    MediaTypeTooLong,
+    #[msg("Custody can only pass from the current custodian to a different wallet")]
+    NotCustodian,
+    #[msg("A listed report cannot be changed; cancel the listing first")]
+    ReportListed,
+    #[msg("Each revenue share of a SOL price must cover the rent-exempt minimum")]
+    RevenueShareBelowRent,
+    #[msg("The team would have fewer voting members than its approval threshold")]
+    ThresholdExceedsVoters,
+    #[msg("Coordinates must already be rounded to the chosen location precision")]
+    CoordinatesNotOnGrid,
+    #[msg("The daily report cap exceeds the most reports an author state tracks")]
+    DailyReportCapTooHigh,
+    #[msg("A team without members cannot be migrated")]
+    EmptyTeam,
+    #[msg("The proposal already holds the most approvals it can record")]
+    TooManyApprovals,
+}
+
+#[derive(Accounts)]
+pub struct CreateCyberTeam<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(init, payer = authority, space = CyberTeam::SPACE)]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        init,
+        payer = authority,
+        space = TeamMembership::SPACE,
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub owner_membership: Account<'info, TeamMembership>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_cyber_team(ctx: Context<CreateCyberTeam>, name: String, open: bool) -> Result<()> {
+    require!(
+        name.len() <= CyberTeam::MAX_NAME_LEN,
+        IntelMarketError::TeamNameTooLong
+    );
+    let team = &mut ctx.accounts.cyber_team;
+    team.version = CyberTeam::VERSION;
+    team.authority = *ctx.accounts.authority.key;
+    team.name = name;
+    team.member_count = 1;
+    team.voter_count = 1;
+    team.open = open;
+    team.threshold = 1;
+    team.proposal_count = 0;
+
+    let membership = &mut ctx.accounts.owner_membership;
+    membership.team = team.key();
+    membership.member = team.authority;
+    membership.role = TeamRole::Admin;
+    membership.payer = team.authority;
+    membership.joined_at = Clock::get()?.unix_timestamp;
+    membership.bump = ctx.bumps.owner_membership;
+
+    emit!(CyberTeamCreated {
+        team: team.key(),
+        authority: team.authority,
+        name: team.name.clone(),
+        open: team.open,
+    });
+    emit!(TeamMemberAdded {
+        team: team.key(),
+        member: team.authority,
+        role: TeamRole::Admin,
+        added_by: team.authority,
+    });
+    Ok(())
+}
+
+// Members join closed teams by invitation: an admin invites a wallet with a role, and the
+// invitee becomes a member only by signing accept_invitation before the invitation expires.
+// Declined or expired invitations are closed back to whoever paid for them.
+
+// Invitation PDA, seeds = ["invitation", team, invitee]
+#[account]
+pub struct TeamInvitation {
+    pub team: Pubkey,
+    pub invitee: Pubkey,
+    pub role: TeamRole,
+    pub inviter: Pubkey, // Inviting admin, or the proposal for multisig teams
+    pub payer: Pubkey,   // Receives the rent back when the invitation is closed
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl TeamInvitation {
+    pub const SEED: &'static [u8] = b"invitation";
+    pub const TTL_SECS: i64 = 7 * 24 * 60 * 60;
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 32 + 32 + 8 + 1;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn issue_invitation(
+    invitation: &mut Account<TeamInvitation>,
+    team: Pubkey,
+    invitee: Pubkey,
+    role: TeamRole,
+    inviter: Pubkey,
+    payer: Pubkey,
+    bump: u8,
+    now: i64,
+) {
+    invitation.team = team;
+    invitation.invitee = invitee;
+    invitation.role = role;
+    invitation.inviter = inviter;
+    invitation.payer = payer;
+    invitation.expires_at = now + TeamInvitation::TTL_SECS;
+    invitation.bump = bump;
+
+    emit!(TeamInvitationCreated {
+        team,
+        invitee,
+        role,
+        inviter,
+        expires_at: invitation.expires_at,
+    });
+}
+
+#[derive(Accounts)]
+#[instruction(invitee: Pubkey)]
+pub struct InviteMember<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), admin.key().as_ref()],
+        bump = admin_membership.bump
+    )]
+    pub admin_membership: Account<'info, TeamMembership>,
+    #[account(
+        init,
+        payer = admin,
+        space = TeamInvitation::SPACE,
+        seeds = [TeamInvitation::SEED, cyber_team.key().as_ref(), invitee.as_ref()],
+        bump
+    )]
+    pub invitation: Account<'info, TeamInvitation>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn invite_member(ctx: Context<InviteMember>, invitee: Pubkey, role: TeamRole) -> Result<()> {
+    let team = &ctx.accounts.cyber_team;
+    team.require_single_signer()?;
+    require!(
+        can_manage(team, &ctx.accounts.admin_membership, role),
+        IntelMarketError::Unauthorized
+    );
+    let admin = ctx.accounts.admin.key();
+    issue_invitation(
+        &mut ctx.accounts.invitation,
+        team.key(),
+        invitee,
+        role,
+        admin,
+        admin,
+        ctx.bumps.invitation,
+        Clock::get()?.unix_timestamp,
+    );
+    Ok(())
+}
+
+// The invitee pays for their own membership account
+#[derive(Accounts)]
+pub struct AcceptInvitation<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        mut,
+        close = payer,
+        has_one = invitee,
+        seeds = [TeamInvitation::SEED, cyber_team.key().as_ref(), invitee.key().as_ref()],
+        bump = invitation.bump
+    )]
+    pub invitation: Account<'info, TeamInvitation>,
+    #[account(
+        init,
+        payer = invitee,
+        space = TeamMembership::SPACE,
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), invitee.key().as_ref()],
+        bump
+    )]
+    pub membership: Account<'info, TeamMembership>,
+    /// CHECK: rent refund destination, must match the invitation's payer
+    #[account(mut, address = invitation.payer)]
+    pub payer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub invitee: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn accept_invitation(ctx: Context<AcceptInvitation>) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    let invitation = &ctx.accounts.invitation;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now < invitation.expires_at,
+        IntelMarketError::InvitationExpired
+    );
+    team.member_count += 1;
+    team.record_role_change(None, Some(invitation.role))?;
+
+    let membership = &mut ctx.accounts.membership;
+    membership.team = team.key();
+    membership.member = invitation.invitee;
+    membership.role = invitation.role;
+    membership.payer = invitation.invitee;
+    membership.joined_at = now;
+    membership.bump = ctx.bumps.membership;
+
+    emit!(TeamMemberAdded {
+        team: team.key(),
+        member: membership.member,
+        role: membership.role,
+        added_by: invitation.inviter,
+    });
+    Ok(())
+}
+
+// The invitee may decline at any time; anyone may close an invitation once it has expired
+#[derive(Accounts)]
+pub struct CloseInvitation<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [TeamInvitation::SEED, invitation.team.as_ref(), invitation.invitee.as_ref()],
+        bump = invitation.bump
+    )]
+    pub invitation: Account<'info, TeamInvitation>,
+    /// CHECK: rent refund destination, must match the invitation's payer
+    #[account(mut, address = invitation.payer)]
+    pub payer: UncheckedAccount<'info>,
+    pub signer: Signer<'info>,
+}
+
+pub fn close_invitation(ctx: Context<CloseInvitation>) -> Result<()> {
+    let invitation = &ctx.accounts.invitation;
+    let declined = ctx.accounts.signer.key() == invitation.invitee;
+    require!(
+        declined || Clock::get()?.unix_timestamp >= invitation.expires_at,
+        IntelMarketError::InvitationNotExpired
+    );
+    emit!(TeamInvitationClosed {
+        team: invitation.team,
+        invitee: invitation.invitee,
+        declined,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetMemberRole<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), admin.key().as_ref()],
+        bump = admin_membership.bump
+    )]
+    pub admin_membership: Account<'info, TeamMembership>,
+    #[account(
+        mut,
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), membership.member.as_ref()],
+        bump = membership.bump
+    )]
+    pub membership: Account<'info, TeamMembership>,
+    pub admin: Signer<'info>,
+}
+
+pub fn set_member_role(ctx: Context<SetMemberRole>, role: TeamRole) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    let admin = &ctx.accounts.admin_membership;
+    let membership = &mut ctx.accounts.membership;
+    team.require_single_signer()?;
+    require!(
+        membership.member != team.authority,
+        IntelMarketError::Unauthorized
+    );
+    require!(
+        can_manage(team, admin, membership.role) && can_manage(team, admin, role),
+        IntelMarketError::Unauthorized
+    );
+    team.record_role_change(Some(membership.role), Some(role))?;
+    membership.role = role;
+
+    emit!(TeamMemberRoleChanged {
+        team: team.key(),
+        member: membership.member,
+        role,
+        changed_by: admin.member,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveMember<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), admin.key().as_ref()],
+        bump = admin_membership.bump
+    )]
+    pub admin_membership: Account<'info, TeamMembership>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), membership.member.as_ref()],
+        bump = membership.bump
+    )]
+    pub membership: Account<'info, TeamMembership>,
+    /// CHECK: rent refund destination, must match the membership's payer
+    #[account(mut, address = membership.payer)]
+    pub payer: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+pub fn remove_member(ctx: Context<RemoveMember>) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    let membership = &ctx.accounts.membership;
+    team.require_single_signer()?;
+    require!(
+        membership.member != team.authority,
+        IntelMarketError::CannotRemoveOwner
+    );
+    require!(
+        can_manage(team, &ctx.accounts.admin_membership, membership.role),
+        IntelMarketError::Unauthorized
+    );
+    team.member_count -= 1;
+    team.record_role_change(Some(membership.role), None)?;
+
+    emit!(TeamMemberRemoved {
+        team: team.key(),
+        member: membership.member,
+        removed_by: *ctx.accounts.admin.key,
+    });
+    Ok(())
+}
+
+// Self-service join for open teams; new members start as observers
+#[derive(Accounts)]
+pub struct JoinCyberTeam<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        init,
+        payer = member,
+        space = TeamMembership::SPACE,
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), member.key().as_ref()],
+        bump
+    )]
+    pub membership: Account<'info, TeamMembership>,
+    #[account(mut)]
+    pub member: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn join_cyber_team(ctx: Context<JoinCyberTeam>) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    require!(team.open, IntelMarketError::TeamClosed);
+    team.member_count += 1;
+
+    let membership = &mut ctx.accounts.membership;
+    membership.team = team.key();
+    membership.member = *ctx.accounts.member.key;
+    membership.role = TeamRole::Observer;
+    membership.payer = *ctx.accounts.member.key;
+    membership.joined_at = Clock::get()?.unix_timestamp;
+    membership.bump = ctx.bumps.membership;
+
+    emit!(TeamMemberAdded {
+        team: team.key(),
+        member: membership.member,
+        role: membership.role,
+        added_by: membership.member,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct LeaveCyberTeam<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), member.key().as_ref()],
+        bump = membership.bump
+    )]
+    pub membership: Account<'info, TeamMembership>,
+    /// CHECK: rent refund destination, must match the membership's payer
+    #[account(mut, address = membership.payer)]
+    pub payer: UncheckedAccount<'info>,
+    pub member: Signer<'info>,
+}
+
+pub fn leave_cyber_team(ctx: Context<LeaveCyberTeam>) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    require!(
+        *ctx.accounts.member.key != team.authority,
+        IntelMarketError::CannotRemoveOwner
+    );
+    team.member_count -= 1;
+    team.record_role_change(Some(ctx.accounts.membership.role), None)?;
+
+    emit!(TeamMemberRemoved {
+        team: team.key(),
+        member: *ctx.accounts.member.key,
+        removed_by: *ctx.accounts.member.key,
+    });
+    Ok(())
+}
+
+// Enumerating team members is done off-chain with account filters on TeamMembership:
+//   const members = await program.account.teamMembership.all([
+//     { memcmp: { offset: 8, bytes: cyberTeam.toBase58() } }, // TeamMembership::TEAM_OFFSET
+//   ]);
+// A single membership is fetched directly from its PDA:
+//   PublicKey.findProgramAddressSync([Buffer.from("membership"), team.toBuffer(), member.toBuffer()], programId)
+
+// M-of-N team administration. Admins and analysts open proposals for membership changes,
+// renames and threshold changes; once `threshold` of them approve before the proposal
+// expires, anyone may execute it. Executed proposals are closed back to the proposer,
+// stale ones can be closed by anyone after expiry. Removals, departures and demotions to
+// observer may never leave fewer voting members than the threshold.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum ProposalAction {
+    AddMember { member: Pubkey, role: TeamRole },
+    RemoveMember { member: Pubkey },
+    ChangeRole { member: Pubkey, role: TeamRole },
+    Rename { name: String },
+    SetThreshold { threshold: u8 },
+}
+
+// Proposal PDA, seeds = ["proposal", team, index (u64 LE)]
+#[account]
+pub struct TeamProposal {
+    pub team: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey, // Receives the rent back when the proposal is closed
+    pub action: ProposalAction,
+    pub approvals: Vec<Pubkey>, // Voting members who approved, proposer included
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl TeamProposal {
+    pub const SEED: &'static [u8] = b"proposal";
+    pub const MAX_APPROVALS: usize = 16;
+    pub const TTL_SECS: i64 = 7 * 24 * 60 * 60;
+    // Largest action is Rename with a full-length name
+    pub const SPACE: usize = 8
+        + 32
+        + 8
+        + 32
+        + (1 + 4 + CyberTeam::MAX_NAME_LEN)
+        + (4 + 32 * Self::MAX_APPROVALS)
+        + 8
+        + 1;
+
+    // Only approvers who are still voting members count toward the threshold
+    pub fn is_approved(&self, team: &CyberTeam, approvers: &[TeamMembership]) -> bool {
+        approvers.len() >= team.threshold.max(1) as usize
+    }
+
+    // Current memberships of the approvers who can still vote, passed to the execute
+    // instructions as remaining accounts. Approvals from members who have since left or
+    // become observers do not count.
+    pub fn current_approvers(
+        &self,
+        team: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> Result<Vec<TeamMembership>> {
+        let mut approvers = TeamMembership::load_all(team, accounts)?;
+        approvers.retain(|m| m.role.votes() && self.approvals.contains(&m.member));
+        Ok(approvers)
+    }
+
+    // Executable once enough current approvers remain, and only until the proposal expires.
+    // At a threshold of 1 one of the approvers must also be allowed to make the change alone.
+    pub fn require_executable(
+        &self,
+        team: &CyberTeam,
+        approvers: &[TeamMembership],
+        target_role: Option<TeamRole>,
+        now: i64,
+    ) -> Result<()> {
+        require!(now < self.expires_at, IntelMarketError::ProposalExpired);
+        require!(
+            self.is_approved(team, approvers),
+            IntelMarketError::ProposalNotApproved
+        );
+        if team.threshold <= 1 {
+            require!(
+                approvers.iter().any(|approver| can_act_alone(
+                    team,
+                    approver,
+                    &self.action,
+                    target_role
+                )),
+                IntelMarketError::Unauthorized
+            );
+        }
+        Ok(())
+    }
+}
+
+pub fn require_voting_member(
+    team: &Pubkey,
+    membership: &TeamMembership,
+    signer: &Pubkey,
+) -> Result<()> {
+    require!(
+        membership.team == *team && membership.member == *signer && membership.role.votes(),
+        IntelMarketError::Unauthorized
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateTeamProposal<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), proposer.key().as_ref()],
+        bump = proposer_membership.bump
+    )]
+    pub proposer_membership: Account<'info, TeamMembership>,
+    // Membership of the member a RemoveMember or ChangeRole proposal targets; required for
+    // those actions while the team's threshold is 1
+    pub target_membership: Option<Account<'info, TeamMembership>>,
+    #[account(
+        init,
+        payer = proposer,
+        space = TeamProposal::SPACE,
+        seeds = [
+            TeamProposal::SEED,
+            cyber_team.key().as_ref(),
+            &cyber_team.proposal_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, TeamProposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_team_proposal(
+    ctx: Context<CreateTeamProposal>,
+    action: ProposalAction,
+) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    let proposer = ctx.accounts.proposer.key();
+    require_voting_member(&team.key(), &ctx.accounts.proposer_membership, &proposer)?;
+    match &action {
+        ProposalAction::RemoveMember { member } | ProposalAction::ChangeRole { member, .. } => {
+            require!(
+                *member != team.authority,
+                IntelMarketError::CannotRemoveOwner
+            );
+        }
+        ProposalAction::Rename { name } => {
+            require!(
+                name.len() <= CyberTeam::MAX_NAME_LEN,
+                IntelMarketError::TeamNameTooLong
+            );
+        }
+        ProposalAction::SetThreshold { threshold } => {
+            require!(
+                *threshold >= 1 && *threshold as usize <= TeamProposal::MAX_APPROVALS,
+                IntelMarketError::InvalidThreshold
+            );
+        }
+        ProposalAction::AddMember { .. } => {}
+    }
+    // A single approval executes the proposal, so the proposer must be able to act alone
+    if team.threshold <= 1 {
+        let target_role = match (&action, &ctx.accounts.target_membership) {
+            (
+                ProposalAction::RemoveMember { member } | ProposalAction::ChangeRole { member, .. },
+                Some(target),
+            ) => {
+                require!(
+                    target.team == team.key() && target.member == *member,
+                    IntelMarketError::ProposalActionMismatch
+                );
+                Some(target.role)
+            }
+            _ => None,
+        };
+        require!(
+            can_act_alone(
+                team,
+                &ctx.accounts.proposer_membership,
+                &action,
+                target_role
+            ),
+            IntelMarketError::Unauthorized
+        );
+    }
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.team = team.key();
+    proposal.index = team.proposal_count;
+    proposal.proposer = proposer;
+    proposal.action = action;
+    proposal.approvals = vec![proposer];
+    proposal.expires_at = Clock::get()?.unix_timestamp + TeamProposal::TTL_SECS;
+    proposal.bump = ctx.bumps.proposal;
+    team.proposal_count += 1;
+
+    emit!(TeamProposalCreated {
+        team: proposal.team,
+        proposal: proposal.key(),
+        proposer,
+        action: proposal.action.clone(),
+        expires_at: proposal.expires_at,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ApproveTeamProposal<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), approver.key().as_ref()],
+        bump = approver_membership.bump
+    )]
+    pub approver_membership: Account<'info, TeamMembership>,
+    #[account(
+        mut,
+        constraint = proposal.team == cyber_team.key() @ IntelMarketError::Unauthorized,
+        seeds = [TeamProposal::SEED, cyber_team.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, TeamProposal>,
+    pub approver: Signer<'info>,
+}
+
+pub fn approve_team_proposal(ctx: Context<ApproveTeamProposal>) -> Result<()> {
+    let approver = ctx.accounts.approver.key();
+    require_voting_member(
+        &ctx.accounts.cyber_team.key(),
+        &ctx.accounts.approver_membership,
+        &approver,
+    )?;
+    let proposal = &mut ctx.accounts.proposal;
+    require!(
+        Clock::get()?.unix_timestamp < proposal.expires_at,
+        IntelMarketError::ProposalExpired
+    );
+    require!(
+        !proposal.approvals.contains(&approver),
+        IntelMarketError::AlreadyApproved
+    );
+    require!(
+        proposal.approvals.len() < TeamProposal::MAX_APPROVALS,
+        IntelMarketError::TooManyApprovals
+    );
+    proposal.approvals.push(approver);
+
+    emit!(TeamProposalApproved {
+        team: proposal.team,
+        proposal: proposal.key(),
+        approver,
+        approvals: proposal.approvals.len() as u8,
+    });
+    Ok(())
+}
+
+// The execute instructions take the approvers' memberships as remaining accounts, so every
+// approval is checked against the approver's current membership and role.
+
+// An approved AddMember proposal invites the member; they still have to accept
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct ExecuteAddMemberProposal<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        mut,
+        close = proposer,
+        constraint = proposal.team == cyber_team.key() @ IntelMarketError::Unauthorized,
+        has_one = proposer,
+        seeds = [TeamProposal::SEED, cyber_team.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, TeamProposal>,
+    #[account(
+        init,
+        payer = executor,
+        space = TeamInvitation::SPACE,
+        seeds = [TeamInvitation::SEED, cyber_team.key().as_ref(), member.as_ref()],
+        bump
+    )]
+    pub invitation: Account<'info, TeamInvitation>,
+    /// CHECK: rent refund destination, must match the proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn execute_add_member_proposal(
+    ctx: Context<ExecuteAddMemberProposal>,
+    member: Pubkey,
+) -> Result<()> {
+    let team = &ctx.accounts.cyber_team;
+    let proposal = &ctx.accounts.proposal;
+    let now = Clock::get()?.unix_timestamp;
+    let ProposalAction::AddMember {
+        member: proposed,
+        role,
+    } = proposal.action
+    else {
+        return err!(IntelMarketError::ProposalActionMismatch);
+    };
+    require!(proposed == member, IntelMarketError::ProposalActionMismatch);
+    let approvers = proposal.current_approvers(&team.key(), ctx.remaining_accounts)?;
+    proposal.require_executable(team, &approvers, None, now)?;
+
+    issue_invitation(
+        &mut ctx.accounts.invitation,
+        team.key(),
+        member,
+        role,
+        proposal.key(),
+        ctx.accounts.executor.key(),
+        ctx.bumps.invitation,
+        now,
+    );
+    emit!(TeamProposalExecuted {
+        team: team.key(),
+        proposal: proposal.key(),
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRemoveMemberProposal<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        mut,
+        close = proposer,
+        constraint = proposal.team == cyber_team.key() @ IntelMarketError::Unauthorized,
+        has_one = proposer,
+        seeds = [TeamProposal::SEED, cyber_team.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, TeamProposal>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [TeamMembership::SEED, cyber_team.key().as_ref(), membership.member.as_ref()],
+        bump = membership.bump
+    )]
+    pub membership: Account<'info, TeamMembership>,
+    /// CHECK: rent refund destination, must match the proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    /// CHECK: rent refund destination, must match the membership's payer
+    #[account(mut, address = membership.payer)]
+    pub payer: UncheckedAccount<'info>,
+    pub executor: Signer<'info>,
+}
+
+pub fn execute_remove_member_proposal(ctx: Context<ExecuteRemoveMemberProposal>) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    let proposal = &ctx.accounts.proposal;
+    let membership = &ctx.accounts.membership;
+    let member = membership.member;
+    require!(
+        proposal.action == ProposalAction::RemoveMember { member },
+        IntelMarketError::ProposalActionMismatch
+    );
+    let approvers = proposal.current_approvers(&team.key(), ctx.remaining_accounts)?;
+    proposal.require_executable(
+        team,
+        &approvers,
+        Some(membership.role),
+        Clock::get()?.unix_timestamp,
+    )?;
+    team.member_count -= 1;
+    team.record_role_change(Some(membership.role), None)?;
+
+    emit!(TeamProposalExecuted {
+        team: team.key(),
+        proposal: proposal.key(),
+    });
+    emit!(TeamMemberRemoved {
+        team: team.key(),
+        member,
+        removed_by: proposal.key(),
+    });
+    Ok(())
+}
+
+// Executes ChangeRole (requires the member's `membership`), Rename and SetThreshold
+#[derive(Accounts)]
+pub struct ExecuteTeamProposal<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        constraint = cyber_team.version == CyberTeam::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub cyber_team: Account<'info, CyberTeam>,
+    #[account(
+        mut,
+        close = proposer,
+        constraint = proposal.team == cyber_team.key() @ IntelMarketError::Unauthorized,
+        has_one = proposer,
+        seeds = [TeamProposal::SEED, cyber_team.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, TeamProposal>,
+    #[account(mut)]
+    pub membership: Option<Account<'info, TeamMembership>>,
+    /// CHECK: rent refund destination, must match the proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub executor: Signer<'info>,
+}
+
+pub fn execute_team_proposal(ctx: Context<ExecuteTeamProposal>) -> Result<()> {
+    let team = &mut ctx.accounts.cyber_team;
+    let proposal = &ctx.accounts.proposal;
+    let approvers = proposal.current_approvers(&team.key(), ctx.remaining_accounts)?;
+    let target_role = ctx.accounts.membership.as_ref().map(|m| m.role);
+    proposal.require_executable(team, &approvers, target_role, Clock::get()?.unix_timestamp)?;
+
+    match &proposal.action {
+        ProposalAction::ChangeRole { member, role } => {
+            let membership = ctx
+                .accounts
+                .membership
+                .as_mut()
+                .ok_or(IntelMarketError::ProposalActionMismatch)?;
+            require!(
+                membership.team == team.key() && membership.member == *member,
+                IntelMarketError::ProposalActionMismatch
+            );
+            team.record_role_change(Some(membership.role), Some(*role))?;
+            membership.role = *role;
+            emit!(TeamMemberRoleChanged {
+                team: team.key(),
+                member: *member,
+                role: *role,
+                changed_by: proposal.key(),
+            });
+        }
+        ProposalAction::Rename { name } => {
+            team.name = name.clone();
+        }
+        ProposalAction::SetThreshold { threshold } => {
+            require!(
+                *threshold as u32 <= team.voter_count,
+                IntelMarketError::ThresholdExceedsVoters
+            );
+            team.threshold = *threshold;
+        }
+        ProposalAction::AddMember { .. } | ProposalAction::RemoveMember { .. } => {
+            return err!(IntelMarketError::ProposalActionMismatch);
+        }
+    }
+
+    emit!(TeamProposalExecuted {
+        team: team.key(),
+        proposal: proposal.key(),
+    });
+    emit!(CyberTeamUpdated {
+        team: team.key(),
+        name: team.name.clone(),
+        threshold: team.threshold,
+    });
+    Ok(())
+}
+
+// Stale proposals can be closed by anyone once expired; rent goes back to the proposer
+#[derive(Accounts)]
+pub struct CloseExpiredProposal<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [TeamProposal::SEED, proposal.team.as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, TeamProposal>,
+    /// CHECK: rent refund destination, must match the proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+pub fn close_expired_proposal(ctx: Context<CloseExpiredProposal>) -> Result<()> {
+    let proposal = &ctx.accounts.proposal;
+    require!(
+        Clock::get()?.unix_timestamp >= proposal.expires_at,
+        IntelMarketError::ProposalNotExpired
+    );
+    emit!(TeamProposalExpired {
+        team: proposal.team,
+        proposal: proposal.key(),
+    });
+    Ok(())
+}
+
+// Account versioning. IntelReport and CyberTeam lead with a layout version byte. Version 1
+// is the layout the program was first deployed with: reports with float coordinates and
+// free-form string tags, teams with an inline member list. A v1 account can still happen
+// to deserialize as the current type, with every field shifted, so each instruction checks
+// the version and a v1 account must be upgraded in place with migrate_intel_report /
+// migrate_cyber_team before any other instruction accepts it. Future layout changes bump
+// VERSION, add the old layout below and extend load_versioned.
+
+// IntelReport as first deployed: a keypair account allocated at a fixed size
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct IntelReportV1 {
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timestamp: i64, // Author-supplied
+    pub author: Pubkey,
+}
+
+impl IntelReportV1 {
+    pub const SPACE: usize = 8 + 256 + 1024 + 64 + 8 + 8 + 8 + 32;
+
+    // The report in the current layout. Coordinates are rounded to whole microdegrees at
+    // Exact precision and the geohash is computed from them. Each tag is mapped by its
+    // canonical name onto `registered` and dropped when no registered tag matches. The v1
+    // timestamp was supplied by the author, so it becomes observed_at and created_at is
+    // the time of the migration. The account's address was not derived from a sequence,
+    // so sequence and bump are 0.
+    pub fn upgrade(self, registered: &[Tag], now: i64) -> Result<IntelReport> {
+        let latitude = to_microdegrees(self.latitude, IntelReport::MAX_LATITUDE)
+            .ok_or(IntelMarketError::LatitudeOutOfRange)?;
+        let longitude = to_microdegrees(self.longitude, IntelReport::MAX_LONGITUDE)
+            .ok_or(IntelMarketError::LongitudeOutOfRange)?;
+        let mut tags: Vec<u16> = Vec::new();
+        for name in &self.tags {
+            let name = canonical_tag_name(name);
+            if let Some(tag) = registered.iter().find(|tag| tag.name == name) {
+                if !tags.contains(&tag.id) {
+                    tags.push(tag.id);
+                }
+            }
+        }
+        Ok(IntelReport {
+            version: IntelReport::VERSION,
+            geohash: geohash_encode(latitude, longitude),
+            title: self.title,
+            content: ReportContent::Inline(self.content),
+            tags,
+            latitude,
+            longitude,
+            location_precision: LocationPrecision::Exact,
+            observed_at: self.timestamp,
+            created_at: now,
+            author: self.author,
+            revision: 1,
+            previous: None,
+            superseded_by: None,
+            sequence: 0,
+            bump: 0,
+            classification: Classification::Unclassified,
+            team: None,
+            geo_indexed: false,
+        })
+    }
+}
+
+// Degrees as whole microdegrees, or None for NaN and anything beyond +/-max
+fn to_microdegrees(degrees: f64, max: i32) -> Option<i32> {
+    let microdegrees = (degrees * 1e6).round();
+    (microdegrees.abs() <= max as f64).then_some(microdegrees as i32)
+}
+
+// Whether `data` starts with a full base32 geohash
+fn starts_with_geohash(data: &[u8]) -> bool {
+    data.len() >= IntelReport::GEOHASH_LEN
+        && data[..IntelReport::GEOHASH_LEN]
+            .iter()
+            .all(|c| GEOHASH_BASE32.contains(c))
+}
+
+// An IntelReport account decoded in whichever layout it holds
+pub enum ReportLayout {
+    V1(IntelReportV1),
+    Current(Box<IntelReport>),
+}
+
+impl IntelReport {
+    // Layouts are recognized positively: current accounts start with VERSION followed by
+    // the geohash, v1 accounts have their fixed size and decode as IntelReportV1. Anything
+    // else is rejected rather than guessed at.
+    pub fn load_versioned(data: &[u8]) -> Result<ReportLayout> {
+        require!(
+            data.len() > 8 && data[..8] == IntelReport::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        let fields = &data[8..];
+        if fields[0] == IntelReport::VERSION && starts_with_geohash(&fields[1..]) {
+            Ok(ReportLayout::Current(Box::new(
+                IntelReport::try_deserialize(&mut &data[..])?,
+            )))
+        } else if data.len() == IntelReportV1::SPACE {
+            IntelReportV1::deserialize(&mut &fields[..])
+                .map(ReportLayout::V1)
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+        } else {
+            err!(IntelMarketError::UnsupportedAccountVersion)
+        }
+    }
+}
+
+// CyberTeam as first deployed: a keypair account listing its members inline, creator
+// first. Members had no roles, and any of them could be removed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CyberTeamV1 {
+    pub name: String,
+    pub members: Vec<Pubkey>,
+}
+
+impl CyberTeamV1 {
+    pub const SPACE: usize = 8 + 4 + 256 + (4 + 32 * 9);
+}
+
+// A CyberTeam account decoded in whichever layout it holds
+pub enum TeamLayout {
+    V1(CyberTeamV1),
+    Current(CyberTeam),
+}
+
+impl CyberTeam {
+    // Team accounts are allocated at a fixed size, so the layout is told apart by length
+    pub fn load_versioned(data: &[u8]) -> Result<TeamLayout> {
+        require!(
+            data.len() > 8 && data[..8] == CyberTeam::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        match data.len() {
+            CyberTeam::SPACE if data[8] == CyberTeam::VERSION => Ok(TeamLayout::Current(
+                CyberTeam::try_deserialize(&mut &data[..])?,
+            )),
+            CyberTeamV1::SPACE => CyberTeamV1::deserialize(&mut &data[8..])
+                .map(TeamLayout::V1)
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize.into()),
+            _ => err!(IntelMarketError::UnsupportedAccountVersion),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    /// CHECK: decoded by hand, older layouts do not deserialize as the current type
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// The registered Tag accounts to map the report's string tags onto are passed as remaining
+// accounts. Since leaving one out drops the tag, only the author may migrate a report. The
+// result must pass the same checks as a newly created report.
+pub fn migrate_intel_report(ctx: Context<MigrateAccount>) -> Result<()> {
+    let info = ctx.accounts.account.to_account_info();
+    let ReportLayout::V1(v1) = IntelReport::load_versioned(&info.try_borrow_data()?)? else {
+        return err!(IntelMarketError::AlreadyMigrated);
+    };
+    require!(
+        v1.author == ctx.accounts.payer.key(),
+        IntelMarketError::Unauthorized
+    );
+    let registered = Tag::load_all(ctx.remaining_accounts)?;
+    let report = v1.upgrade(&registered, Clock::get()?.unix_timestamp)?;
+    validate_report_fields(
+        &ctx.accounts.config,
+        &report.title,
+        &report.content,
+        &report.tags,
+        report.latitude,
+        report.longitude,
+        report.location_precision,
+    )?;
+    let new_space = IntelReport::space(&report.title, &report.content, &report.tags);
+    resize_account(
+        &info,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        new_space,
+    )?;
+    report.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        account: info.key(),
+        from_version: 1,
+        to_version: IntelReport::VERSION,
+    });
+    Ok(())
+}
+
+// A v1 team keeps its name and members. The first listed member, its creator unless they
+// were removed, becomes the owner and an admin; everyone else becomes an analyst, so every
+// former member keeps a vote. Each member's TeamMembership PDA is passed as a writable
+// remaining account, in the order of the v1 list, and is created here at the payer's
+// expense. The migration makes no choices, so anyone may run it.
+pub fn migrate_cyber_team<'info>(
+    ctx: Context<'_, '_, '_, 'info, MigrateAccount<'info>>,
+) -> Result<()> {
+    let info = ctx.accounts.account.to_account_info();
+    let TeamLayout::V1(v1) = CyberTeam::load_versioned(&info.try_borrow_data()?)? else {
+        return err!(IntelMarketError::AlreadyMigrated);
+    };
+    let Some(owner) = v1.members.first().copied() else {
+        return err!(IntelMarketError::EmptyTeam);
+    };
+    require!(
+        v1.name.len() <= CyberTeam::MAX_NAME_LEN,
+        IntelMarketError::TeamNameTooLong
+    );
+    require!(
+        ctx.remaining_accounts.len() >= v1.members.len(),
+        ErrorCode::AccountNotEnoughKeys
+    );
+    let now = Clock::get()?.unix_timestamp;
+    for (member, account) in v1.members.iter().zip(ctx.remaining_accounts) {
+        let role = if *member == owner {
+            TeamRole::Admin
+        } else {
+            TeamRole::Analyst
+        };
+        create_membership(
+            account,
+            TeamMembership {
+                team: info.key(),
+                member: *member,
+                role,
+                payer: ctx.accounts.payer.key(),
+                joined_at: now,
+                bump: 0,
+            },
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        emit!(TeamMemberAdded {
+            team: info.key(),
+            member: *member,
+            role,
+            added_by: owner,
+        });
+    }
+
+    let team = CyberTeam {
+        version: CyberTeam::VERSION,
+        authority: owner,
+        name: v1.name,
+        member_count: v1.members.len() as u32,
+        voter_count: v1.members.len() as u32,
+        open: false,
+        threshold: 1,
+        proposal_count: 0,
+    };
+    resize_account(
+        &info,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        CyberTeam::SPACE,
+    )?;
+    team.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        account: info.key(),
+        from_version: 1,
+        to_version: CyberTeam::VERSION,
+    });
+    Ok(())
+}
+
+// Creates `membership` at its PDA, which `account` must be, filling in the bump. Like
+// Anchor's init, lamports already sent to the address do not block the creation.
+fn create_membership<'info>(
+    account: &AccountInfo<'info>,
+    mut membership: TeamMembership,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let (address, bump) = Pubkey::find_program_address(
+        &[
+            TeamMembership::SEED,
+            membership.team.as_ref(),
+            membership.member.as_ref(),
+        ],
+        &crate::ID,
+    );
+    require_keys_eq!(account.key(), address, ErrorCode::ConstraintSeeds);
+    let seeds: &[&[u8]] = &[
+        TeamMembership::SEED,
+        membership.team.as_ref(),
+        membership.member.as_ref(),
+        &[bump],
+    ];
+    let rent = Rent::get()?.minimum_balance(TeamMembership::SPACE);
+    let funded = account.lamports();
+    if funded == 0 {
+        anchor_lang::system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                anchor_lang::system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                &[seeds],
+            ),
+            rent,
+            TeamMembership::SPACE as u64,
+            &crate::ID,
+        )?;
+    } else {
+        if rent > funded {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                rent - funded,
+            )?;
+        }
+        anchor_lang::system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Allocate {
+                    account_to_allocate: account.clone(),
+                },
+                &[seeds],
+            ),
+            TeamMembership::SPACE as u64,
+        )?;
+        anchor_lang::system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Assign {
+                    account_to_assign: account.clone(),
+                },
+                &[seeds],
+            ),
+            &crate::ID,
+        )?;
+    }
+    membership.bump = bump;
+    membership.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+// Intelligence Exchange Market: authors list reports for sale and buyers pay into escrow.
+// A listing holds one buyer's payment at a time; settlement releases it to the seller and
+// grants the buyer access, cancelling the listing refunds any pending buyer.
+// Listings are priced either in lamports or in an SPL mint (e.g. a stablecoin); the
+// `_spl` instructions move tokens through associated token accounts.
+// On settlement the protocol fee (from ProgramConfig) is taken first, and the remainder
+// is split across the listing's revenue shares in the same instruction.
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// Global program config PDA, seeds = ["config"]
+#[account]
+pub struct ProgramConfig {
+    pub admin: Pubkey,                 // May update the config
+    pub fee_bps: u16,                  // Protocol fee taken from each sale, in basis points
+    pub fee_recipient: Option<Pubkey>, // Fee destination; no fee is charged when None
+    pub bump: u8,
+    pub arbiters: Vec<Pubkey>, // Keys allowed to resolve purchase disputes
+    pub arbiter_team: Option<Pubkey>, // CyberTeam whose admins and analysts may also resolve them
+    pub paused: bool, // Incident switch: every instruction except config updates fails while set
+    pub max_content_len: u32, // Largest report body (inline, or encrypted before sealing)
+    pub fees: FeeSchedule, // Flat fees, on top of the per-sale fee_bps
+    pub min_report_interval: u32, // Seconds an author must wait between reports, 0 for no limit
+    pub daily_report_cap: u32, // Reports per author in any 24h span, 0 for no limit
+}
+
+// Flat lamport fees paid to the config's fee_recipient; nothing is charged without one
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct FeeSchedule {
+    pub report_fee: u64,  // Per created or superseding report
+    pub listing_fee: u64, // Per market listing
+}
+
+impl ProgramConfig {
+    pub const SEED: &'static [u8] = b"config";
+    pub const MAX_ARBITERS: usize = 5;
+    // Zeroed space after the fields, so later fields fit without resizing the account
+    pub const RESERVED: usize = 64;
+    pub const SPACE: usize = 8
+        + 32
+        + 2
+        + (1 + 32)
+        + 1
+        + (4 + 32 * Self::MAX_ARBITERS)
+        + (1 + 32)
+        + 1
+        + 4
+        + (8 + 8)
+        + 4
+        + 4
+        + Self::RESERVED;
+    // Reports are created through a CPI, which can allocate at most 10 KiB, so the body
+    // gets what the other fields leave at their largest
+    pub const MAX_CONTENT_LEN_LIMIT: u32 =
+        (MAX_PERMITTED_DATA_INCREASE - IntelReport::MAX_SPACE_WITHOUT_BODY) as u32;
+
+    // Observers of the arbiter team cannot rule on disputes
+    pub fn is_arbiter(&self, key: &Pubkey, membership: Option<&TeamMembership>) -> bool {
+        self.arbiters.contains(key)
+            || membership.is_some_and(|membership| {
+                Some(membership.team) == self.arbiter_team
+                    && membership.member == *key
+                    && membership.role != TeamRole::Observer
+            })
+    }
+
+    pub fn effective_fee_bps(&self) -> u16 {
+        if self.fee_recipient.is_some() {
+            self.fee_bps
+        } else {
+            0
+        }
+    }
+}
+
+// The config can only be created once, so only the program's upgrade authority may
+// create it and become its admin
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ProgramConfig::SPACE,
+        seeds = [ProgramConfig::SEED],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ IntelMarketError::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::IntelMarket>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ IntelMarketError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_config(
+    ctx: Context<InitializeConfig>,
+    fee_bps: u16,
+    fee_recipient: Option<Pubkey>,
+) -> Result<()> {
+    require!(
+        fee_bps as u64 <= BPS_DENOMINATOR,
+        IntelMarketError::InvalidBasisPoints
+    );
+    let config = &mut ctx.accounts.config;
+    config.admin = *ctx.accounts.admin.key;
+    config.fee_bps = fee_bps;
+    config.fee_recipient = fee_recipient;
+    config.bump = ctx.bumps.config;
+    config.arbiters = Vec::new();
+    config.arbiter_team = None;
+    config.paused = false;
+    config.max_content_len = IntelReport::MAX_CONTENT_LEN as u32;
+    config.fees = FeeSchedule::default();
+    config.min_report_interval = 0;
+    config.daily_report_cap = 0;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, has_one = admin, seeds = [ProgramConfig::SEED], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    pub admin: Signer<'info>,
+}
+
+pub fn update_config(
+    ctx: Context<UpdateConfig>,
+    new_admin: Pubkey,
+    fee_bps: u16,
+    fee_recipient: Option<Pubkey>,
+    max_content_len: u32,
+    fees: FeeSchedule,
+) -> Result<()> {
+    require!(
+        fee_bps as u64 <= BPS_DENOMINATOR,
+        IntelMarketError::InvalidBasisPoints
+    );
+    require!(
+        max_content_len <= ProgramConfig::MAX_CONTENT_LEN_LIMIT,
+        IntelMarketError::ContentTooLong
+    );
+    let config = &mut ctx.accounts.config;
+    config.admin = new_admin;
+    config.fee_bps = fee_bps;
+    config.fee_recipient = fee_recipient;
+    config.max_content_len = max_content_len;
+    config.fees = fees;
+    Ok(())
+}
+
+pub fn set_rate_limits(
+    ctx: Context<UpdateConfig>,
+    min_report_interval: u32,
+    daily_report_cap: u32,
+) -> Result<()> {
+    require!(
+        daily_report_cap as usize <= AuthorState::MAX_DAILY_REPORT_CAP,
+        IntelMarketError::DailyReportCapTooHigh
+    );
+    let config = &mut ctx.accounts.config;
+    config.min_report_interval = min_report_interval;
+    config.daily_report_cap = daily_report_cap;
+    Ok(())
+}
+
+// Config updates stay available while paused so the admin can lift the pause
+pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.paused = paused;
+    emit!(ProgramPauseChanged {
+        admin: config.admin,
+        paused,
+        at: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Charges a flat fee from `payer` to the config's fee recipient; returns the amount charged
+pub fn charge_flat_fee<'info>(
+    config: &ProgramConfig,
+    fee: u64,
+    payer: &Signer<'info>,
+    fee_recipient: Option<&AccountInfo<'info>>,
+    system_program: &Program<'info, System>,
+) -> Result<u64> {
+    if fee == 0 || config.fee_recipient.is_none() {
+        return Ok(0);
+    }
+    let fee_recipient = fee_recipient.ok_or(IntelMarketError::FeeAccountMismatch)?;
+    require!(
+        Some(fee_recipient.key()) == config.fee_recipient,
+        IntelMarketError::FeeAccountMismatch
+    );
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.to_account_info(),
+                to: fee_recipient.clone(),
+            },
+        ),
+        fee,
+    )?;
+    Ok(fee)
+}
+
+#[event]
+pub struct ProgramPauseChanged {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub at: i64,
+}
+
+pub fn set_arbiters(
+    ctx: Context<UpdateConfig>,
+    arbiters: Vec<Pubkey>,
+    arbiter_team: Option<Pubkey>,
+) -> Result<()> {
+    require!(
+        arbiters.len() <= ProgramConfig::MAX_ARBITERS,
+        IntelMarketError::TooManyArbiters
+    );
+    let config = &mut ctx.accounts.config;
+    config.arbiters = arbiters;
+    config.arbiter_team = arbiter_team;
+    Ok(())
+}
+
+// One co-author's cut of a listing's proceeds (after the protocol fee)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RevenueShare {
+    pub recipient: Pubkey,
+    pub bps: u16,
+}
+
+// Amounts paid out of one settled purchase
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Payout {
+    pub fee: u64,
+    pub shares: Vec<u64>, // In the same order as Listing::shares
+    pub seller: u64,      // Whole net amount without shares, otherwise only rounding dust
+}
+
+// Listing PDA, seeds = ["listing", intel_report]. Escrowed lamports are held by the listing
+// itself; escrowed tokens by the listing's associated token account for `payment_mint`.
+#[account]
+pub struct Listing {
+    pub seller: Pubkey,               // Report author receiving the proceeds
+    pub intel_report: Pubkey,         // Report being sold
+    pub price: u64,                   // Price in lamports, or in base units of `payment_mint`
+    pub payment_mint: Option<Pubkey>, // SPL mint the price is denominated in; None for SOL
+    pub buyer: Option<Pubkey>,        // Buyer whose payment is currently in escrow
+    pub escrowed_at: i64,             // When the pending payment was made
+    pub sales: u64,                   // Number of settled purchases
+    pub bump: u8,
+    pub dispute_deadline: Option<i64>, // Escrow is frozen until then while a dispute is open
+    pub shares: Vec<RevenueShare>, // Co-author split summing to 10,000 bps; empty pays the seller
+}
+
+impl Listing {
+    pub const SEED: &'static [u8] = b"listing";
+    pub const MAX_SHARES: usize = 8;
+    // Seller may settle without the buyer's confirmation once this long has passed
+    pub const ESCROW_HOLD_SECS: i64 = 3 * 24 * 60 * 60;
+    // How long an open dispute freezes the escrow for the arbiters to rule
+    pub const DISPUTE_WINDOW_SECS: i64 = 7 * 24 * 60 * 60;
+
+    pub fn space(share_count: usize) -> usize {
+        8 + 32 + 32 + 8 + (1 + 32) + (1 + 32) + 8 + 8 + 1 + (1 + 8) + (4 + share_count * (32 + 2))
+    }
+
+    fn open(
+        &mut self,
+        seller: Pubkey,
+        intel_report: Pubkey,
+        price: u64,
+        payment_mint: Option<Pubkey>,
+        shares: Vec<RevenueShare>,
+        bump: u8,
+    ) -> Result<()> {
+        require!(price > 0, IntelMarketError::InvalidPrice);
+        require!(
+            shares.len() <= Self::MAX_SHARES,
+            IntelMarketError::TooManyRevenueShares
+        );
+        require!(
+            shares.is_empty()
+                || (shares.iter().all(|share| share.bps > 0)
+                    && shares.iter().map(|share| share.bps as u64).sum::<u64>() == BPS_DENOMINATOR),
+            IntelMarketError::InvalidBasisPoints
+        );
+        self.shares = shares;
+        self.seller = seller;
+        self.intel_report = intel_report;
+        self.price = price;
+        self.payment_mint = payment_mint;
+        self.buyer = None;
+        self.escrowed_at = 0;
+        self.sales = 0;
+        self.bump = bump;
+        self.dispute_deadline = None;
+        Ok(())
+    }
+
+    // Lamport shares are paid straight to the recipients' wallets, and a wallet that does
+    // not exist yet cannot be funded below the rent-exempt minimum: such a share would make
+    // every settlement of the listing fail. Checked against the fee in force at listing time.
+    pub fn require_rent_exempt_shares(&self, fee_bps: u16, minimum: u64) -> Result<()> {
+        require!(
+            self.payout(self.price, fee_bps)
+                .shares
+                .iter()
+                .all(|amount| *amount >= minimum),
+            IntelMarketError::RevenueShareBelowRent
+        );
+        Ok(())
+    }
+
+    // Shared by the SOL and SPL purchase paths once the payment is in escrow
+    pub fn record_purchase(
+        &mut self,
+        access: &mut ReportAccess,
+        buyer: Pubkey,
+        bump: u8,
+        now: i64,
+    ) -> Result<()> {
+        require!(self.buyer.is_none(), IntelMarketError::EscrowOccupied);
+        require!(buyer != self.seller, IntelMarketError::Unauthorized);
+        self.buyer = Some(buyer);
+        self.escrowed_at = now;
+
+        access.intel_report = self.intel_report;
+        access.buyer = buyer;
+        access.price_paid = self.price;
+        access.status = AccessStatus::Pending;
+        access.purchased_at = now;
+        access.bump = bump;
+        Ok(())
+    }
+
+    // Fee first, then each share of the net amount rounded down; the seller keeps the rest
+    pub fn payout(&self, gross: u64, fee_bps: u16) -> Payout {
+        let fee = (gross as u128 * fee_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        let net = gross - fee;
+        let shares: Vec<u64> = self
+            .shares
+            .iter()
+            .map(|share| (net as u128 * share.bps as u128 / BPS_DENOMINATOR as u128) as u64)
+            .collect();
+        let seller = net - shares.iter().sum::<u64>();
+        Payout {
+            fee,
+            shares,
+            seller,
+        }
+    }
+
+    // The buyer may confirm at any time; the seller only after the hold period and
+    // never while a dispute window is still open
+    pub fn check_settle_authority(
+        &self,
+        access: &ReportAccess,
+        authority: &Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        if *authority != access.buyer {
+            require!(*authority == self.seller, IntelMarketError::Unauthorized);
+            require!(
+                now >= self.escrowed_at + Self::ESCROW_HOLD_SECS,
+                IntelMarketError::EscrowHoldActive
+            );
+            require!(!self.dispute_active(now), IntelMarketError::DisputeActive);
+        }
+        Ok(())
+    }
+
+    pub fn dispute_active(&self, now: i64) -> bool {
+        self.dispute_deadline.is_some_and(|deadline| now < deadline)
+    }
+
+    // Only the pending buyer may dispute, once, and only before the seller could settle.
+    // An unresolved dispute lapses at the deadline and the normal settlement rules resume.
+    pub fn open_dispute(&mut self, buyer: &Pubkey, now: i64) -> Result<i64> {
+        require!(
+            self.buyer == Some(*buyer),
+            IntelMarketError::NoPendingPurchase
+        );
+        require!(
+            self.dispute_deadline.is_none(),
+            IntelMarketError::DisputeAlreadyOpened
+        );
+        require!(
+            now < self.escrowed_at + Self::ESCROW_HOLD_SECS,
+            IntelMarketError::DisputeWindowClosed
+        );
+        let deadline = now + Self::DISPUTE_WINDOW_SECS;
+        self.dispute_deadline = Some(deadline);
+        Ok(deadline)
+    }
+
+    pub fn require_open_dispute(&self) -> Result<()> {
+        require!(
+            self.buyer.is_some() && self.dispute_deadline.is_some(),
+            IntelMarketError::NoActiveDispute
+        );
+        Ok(())
+    }
+
+    // Escrow released to the seller side: the buyer keeps access
+    pub fn complete_sale(&mut self, access: &mut ReportAccess) {
+        self.buyer = None;
+        self.dispute_deadline = None;
+        self.sales += 1;
+        access.status = AccessStatus::Granted;
+    }
+
+    // Escrow returned to the buyer: the listing is free for the next purchase
+    pub fn clear_escrow(&mut self) {
+        self.buyer = None;
+        self.dispute_deadline = None;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AccessStatus {
+    Pending, // Paid into escrow, not yet settled
+    Granted, // Settled; the buyer may fetch the report
+}
+
+// Buyer access record, seeds = ["access", intel_report, buyer]
+#[account]
+pub struct ReportAccess {
+    pub intel_report: Pubkey,
+    pub buyer: Pubkey,
+    pub price_paid: u64,
+    pub status: AccessStatus,
+    pub purchased_at: i64,
+    pub bump: u8,
+}
+
+impl ReportAccess {
+    pub const SEED: &'static [u8] = b"access";
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1 + 8 + 1;
+}
+
+#[derive(Accounts)]
+#[instruction(price: u64, shares: Vec<RevenueShare>)]
+pub struct ListIntelReport<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        constraint = intel_report.author == seller.key() @ IntelMarketError::Unauthorized,
+        constraint = intel_report.superseded_by.is_none() @ IntelMarketError::ReportSuperseded,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    #[account(
+        init,
+        payer = seller,
+        space = Listing::space(shares.len()),
+        seeds = [Listing::SEED, intel_report.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    /// CHECK: fee destination, checked against the config when a flat fee is due
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn list_intel_report(
+    ctx: Context<ListIntelReport>,
+    price: u64,
+    shares: Vec<RevenueShare>,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    listing.open(
+        *ctx.accounts.seller.key,
+        ctx.accounts.intel_report.key(),
+        price,
+        None,
+        shares,
+        ctx.bumps.listing,
+    )?;
+    listing.require_rent_exempt_shares(
+        ctx.accounts.config.effective_fee_bps(),
+        Rent::get()?.minimum_balance(0),
+    )?;
+    charge_flat_fee(
+        &ctx.accounts.config,
+        ctx.accounts.config.fees.listing_fee,
+        &ctx.accounts.seller,
+        ctx.accounts.fee_recipient.as_deref(),
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(ReportListed {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        seller: listing.seller,
+        price,
+        payment_mint: None,
+    });
+    Ok(())
+}
+
+// Lists a report priced in `payment_mint` and creates the listing's escrow token account
+#[derive(Accounts)]
+#[instruction(price: u64, shares: Vec<RevenueShare>)]
+pub struct ListIntelReportSpl<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        constraint = intel_report.author == seller.key() @ IntelMarketError::Unauthorized,
+        constraint = intel_report.superseded_by.is_none() @ IntelMarketError::ReportSuperseded,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    #[account(
+        init,
+        payer = seller,
+        space = Listing::space(shares.len()),
+        seeds = [Listing::SEED, intel_report.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = payment_mint,
+        associated_token::authority = listing
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    /// CHECK: fee destination, checked against the config when a flat fee is due
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn list_intel_report_spl(
+    ctx: Context<ListIntelReportSpl>,
+    price: u64,
+    shares: Vec<RevenueShare>,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    listing.open(
+        *ctx.accounts.seller.key,
+        ctx.accounts.intel_report.key(),
+        price,
+        Some(ctx.accounts.payment_mint.key()),
+        shares,
+        ctx.bumps.listing,
+    )?;
+    charge_flat_fee(
+        &ctx.accounts.config,
+        ctx.accounts.config.fees.listing_fee,
+        &ctx.accounts.seller,
+        ctx.accounts.fee_recipient.as_deref(),
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(ReportListed {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        seller: listing.seller,
+        price,
+        payment_mint: listing.payment_mint,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PurchaseIntelReport<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump,
+        constraint = listing.payment_mint.is_none() @ IntelMarketError::PaymentMintMismatch
+    )]
+    pub listing: Account<'info, Listing>,
+    // Superseded revisions can no longer be bought
+    #[account(
+        address = listing.intel_report,
+        constraint = intel_report.superseded_by.is_none() @ IntelMarketError::ReportSuperseded,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    // Created here so a wallet can only ever hold one purchase per report
+    #[account(
+        init,
+        payer = buyer,
+        space = ReportAccess::SPACE,
+        seeds = [ReportAccess::SEED, listing.intel_report.as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub access: Account<'info, ReportAccess>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn purchase_intel_report(ctx: Context<PurchaseIntelReport>) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: listing.to_account_info(),
+            },
+        ),
+        listing.price,
+    )?;
+    listing.record_purchase(
+        &mut ctx.accounts.access,
+        ctx.accounts.buyer.key(),
+        ctx.bumps.access,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    emit!(ReportPurchased {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        buyer: ctx.accounts.buyer.key(),
+        price: listing.price,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PurchaseIntelReportSpl<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump,
+        constraint = listing.payment_mint == Some(payment_mint.key()) @ IntelMarketError::PaymentMintMismatch
+    )]
+    pub listing: Account<'info, Listing>,
+    // Superseded revisions can no longer be bought
+    #[account(
+        address = listing.intel_report,
+        constraint = intel_report.superseded_by.is_none() @ IntelMarketError::ReportSuperseded,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    #[account(
+        init,
+        payer = buyer,
+        space = ReportAccess::SPACE,
+        seeds = [ReportAccess::SEED, listing.intel_report.as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub access: Account<'info, ReportAccess>,
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = listing
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn purchase_intel_report_spl(ctx: Context<PurchaseIntelReportSpl>) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    token::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.buyer_token.to_account_info(),
+                mint: ctx.accounts.payment_mint.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            },
+        ),
+        listing.price,
+        ctx.accounts.payment_mint.decimals,
+    )?;
+    listing.record_purchase(
+        &mut ctx.accounts.access,
+        ctx.accounts.buyer.key(),
+        ctx.bumps.access,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    emit!(ReportPurchased {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        buyer: ctx.accounts.buyer.key(),
+        price: listing.price,
+    });
+    Ok(())
+}
+
+// Releases the escrow and grants access. The buyer may confirm at any time; the seller
+// may settle alone once the escrow hold period has passed.
+// Remaining accounts: one per revenue share, in listing order (the recipient wallets for
+// SOL listings, their associated token accounts for SPL listings).
+#[derive(Accounts)]
+pub struct SettlePurchase<'info> {
+    #[account(
+        mut,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump,
+        constraint = listing.buyer == Some(access.buyer) @ IntelMarketError::NoPendingPurchase,
+        constraint = listing.payment_mint.is_none() @ IntelMarketError::PaymentMintMismatch
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ReportAccess::SEED, listing.intel_report.as_ref(), access.buyer.as_ref()],
+        bump = access.bump
+    )]
+    pub access: Account<'info, ReportAccess>,
+    // The purchased report must still exist when the escrow is released
+    #[account(
+        address = listing.intel_report,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    /// CHECK: proceeds destination, must match the listing's seller
+    #[account(mut, address = listing.seller)]
+    pub seller: UncheckedAccount<'info>,
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    /// CHECK: protocol fee destination, checked against the config when a fee is due
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    pub authority: Signer<'info>,
+}
+
+pub fn settle_purchase<'info>(
+    ctx: Context<'_, '_, '_, 'info, SettlePurchase<'info>>,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let access = &mut ctx.accounts.access;
+    listing.check_settle_authority(
+        access,
+        ctx.accounts.authority.key,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let payout = release_escrow_lamports(
+        listing,
+        access.price_paid,
+        &ctx.accounts.config,
+        &ctx.accounts.seller,
+        ctx.accounts.fee_recipient.as_deref(),
+        ctx.remaining_accounts,
+    )?;
+
+    listing.complete_sale(access);
+
+    emit!(PurchaseSettled {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        buyer: access.buyer,
+        seller: listing.seller,
+        amount: access.price_paid,
+        fee: payout.fee,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettlePurchaseSpl<'info> {
+    #[account(
+        mut,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump,
+        constraint = listing.buyer == Some(access.buyer) @ IntelMarketError::NoPendingPurchase,
+        constraint = listing.payment_mint == Some(payment_mint.key()) @ IntelMarketError::PaymentMintMismatch
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ReportAccess::SEED, listing.intel_report.as_ref(), access.buyer.as_ref()],
+        bump = access.bump
+    )]
+    pub access: Account<'info, ReportAccess>,
+    // The purchased report must still exist when the escrow is released
+    #[account(
+        address = listing.intel_report,
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = listing
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = listing.seller
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    // The fee recipient's associated token account, required when a fee is due
+    #[account(mut)]
+    pub fee_token: Option<Account<'info, TokenAccount>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn settle_purchase_spl<'info>(
+    ctx: Context<'_, '_, '_, 'info, SettlePurchaseSpl<'info>>,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let access = &mut ctx.accounts.access;
+    listing.check_settle_authority(
+        access,
+        ctx.accounts.authority.key,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let payout = release_escrow_tokens(
+        listing,
+        access.price_paid,
+        &ctx.accounts.config,
+        &ctx.accounts.payment_mint,
+        &ctx.accounts.escrow,
+        &ctx.accounts.seller_token.to_account_info(),
+        ctx.accounts
+            .fee_token
+            .as_ref()
+            .map(|fee| fee.to_account_info()),
+        ctx.remaining_accounts,
+        &ctx.accounts.token_program,
+    )?;
+
+    listing.complete_sale(access);
+
+    emit!(PurchaseSettled {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        buyer: access.buyer,
+        seller: listing.seller,
+        amount: access.price_paid,
+        fee: payout.fee,
+    });
+    Ok(())
+}
+
+// Pays a lamport escrow out of the listing: protocol fee, revenue shares, then the seller
+pub fn release_escrow_lamports<'info>(
+    listing: &Account<'info, Listing>,
+    gross: u64,
+    config: &ProgramConfig,
+    seller: &AccountInfo<'info>,
+    fee_recipient: Option<&AccountInfo<'info>>,
+    share_accounts: &[AccountInfo<'info>],
+) -> Result<Payout> {
+    let payout = listing.payout(gross, config.effective_fee_bps());
+    require!(
+        share_accounts.len() == listing.shares.len(),
+        IntelMarketError::RevenueShareAccountsMismatch
+    );
+    let escrow = listing.to_account_info();
+
+    if payout.fee > 0 {
+        let fee_recipient = fee_recipient.ok_or(IntelMarketError::FeeAccountMismatch)?;
+        require!(
+            Some(fee_recipient.key()) == config.fee_recipient,
+            IntelMarketError::FeeAccountMismatch
+        );
+        move_lamports(&escrow, fee_recipient, payout.fee)?;
+    }
+    for ((share, amount), account) in listing
+        .shares
+        .iter()
+        .zip(&payout.shares)
+        .zip(share_accounts)
+    {
+        require!(
+            account.key() == share.recipient,
+            IntelMarketError::RevenueShareAccountsMismatch
+        );
+        move_lamports(&escrow, account, *amount)?;
+    }
+    move_lamports(&escrow, seller, payout.seller)?;
+    Ok(payout)
+}
+
+// Moves lamports out of a program-owned account (the listing escrow)
+pub fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? -= amount;
+    **to.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+// Moves tokens out of the listing's escrow account, signing as the listing PDA
+pub fn transfer_from_escrow<'info>(
+    listing: &Account<'info, Listing>,
+    payment_mint: &Account<'info, Mint>,
+    escrow: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    amount: u64,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        Listing::SEED,
+        listing.intel_report.as_ref(),
+        &[listing.bump],
+    ]];
+    token::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: escrow.to_account_info(),
+                mint: payment_mint.to_account_info(),
+                to,
+                authority: listing.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        payment_mint.decimals,
+    )
+}
+
+// Pays a token escrow out of the listing's escrow account: fee, shares, then the seller
+#[allow(clippy::too_many_arguments)]
+pub fn release_escrow_tokens<'info>(
+    listing: &Account<'info, Listing>,
+    gross: u64,
+    config: &ProgramConfig,
+    payment_mint: &Account<'info, Mint>,
+    escrow: &Account<'info, TokenAccount>,
+    seller_token: &AccountInfo<'info>,
+    fee_token: Option<AccountInfo<'info>>,
+    share_accounts: &[AccountInfo<'info>],
+    token_program: &Program<'info, Token>,
+) -> Result<Payout> {
+    let payout = listing.payout(gross, config.effective_fee_bps());
+    require!(
+        share_accounts.len() == listing.shares.len(),
+        IntelMarketError::RevenueShareAccountsMismatch
+    );
+    let mint = payment_mint.key();
+    let transfer = |to: AccountInfo<'info>, amount: u64| {
+        transfer_from_escrow(listing, payment_mint, escrow, to, amount, token_program)
+    };
+
+    if payout.fee > 0 {
+        let fee_token = fee_token.ok_or(IntelMarketError::FeeAccountMismatch)?;
+        let expected = config
+            .fee_recipient
+            .map(|recipient| get_associated_token_address(&recipient, &mint));
+        require!(
+            Some(fee_token.key()) == expected,
+            IntelMarketError::FeeAccountMismatch
+        );
+        transfer(fee_token, payout.fee)?;
+    }
+    for ((share, amount), account) in listing
+        .shares
+        .iter()
+        .zip(&payout.shares)
+        .zip(share_accounts)
+    {
+        require!(
+            account.key() == get_associated_token_address(&share.recipient, &mint),
+            IntelMarketError::RevenueShareAccountsMismatch
+        );
+        transfer(account.clone(), *amount)?;
+    }
+    transfer(seller_token.clone(), payout.seller)?;
+    Ok(payout)
+}
+
+// Disputes: the pending buyer may dispute a purchase during the escrow hold period,
+// which freezes the escrow for DISPUTE_WINDOW_SECS. An arbiter (config-listed key or
+// member of the arbiter team) rules either a refund to the buyer or a release to the seller.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DisputeOutcome {
+    Refund,  // Escrow back to the buyer, counted against the author's reputation
+    Release, // Escrow paid out as a normal settlement
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+    pub buyer: Signer<'info>,
+}
+
+pub fn open_dispute(ctx: Context<OpenDispute>) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let buyer = ctx.accounts.buyer.key();
+    let deadline = listing.open_dispute(&buyer, Clock::get()?.unix_timestamp)?;
+
+    emit!(DisputeOpened {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        buyer,
+        deadline,
+    });
+    Ok(())
+}
+
+// Remaining accounts on release: the revenue share accounts, as for settle_purchase
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(
+        mut,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump,
+        constraint = listing.buyer == Some(access.buyer) @ IntelMarketError::NoPendingPurchase,
+        constraint = listing.payment_mint.is_none() @ IntelMarketError::PaymentMintMismatch
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ReportAccess::SEED, listing.intel_report.as_ref(), access.buyer.as_ref()],
+        bump = access.bump
+    )]
+    pub access: Account<'info, ReportAccess>,
+    /// CHECK: refund destination, must match the disputing buyer
+    #[account(mut, address = access.buyer)]
+    pub buyer: UncheckedAccount<'info>,
+    /// CHECK: proceeds destination, must match the listing's seller
+    #[account(mut, address = listing.seller)]
+    pub seller: UncheckedAccount<'info>,
+    // Counts a refund against the seller when their reputation account exists
+    #[account(
+        mut,
+        seeds = [AuthorReputation::SEED, listing.seller.as_ref()],
+        bump = reputation.bump
+    )]
+    pub reputation: Option<Account<'info, AuthorReputation>>,
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    /// CHECK: protocol fee destination, checked against the config when a fee is due
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    // The arbiter's membership in the config's arbiter team, unless listed in the config
+    pub arbiter_membership: Option<Account<'info, TeamMembership>>,
+    pub arbiter: Signer<'info>,
+}
+
+pub fn resolve_dispute<'info>(
+    ctx: Context<'_, '_, '_, 'info, ResolveDispute<'info>>,
+    outcome: DisputeOutcome,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let access = &mut ctx.accounts.access;
+    listing.require_open_dispute()?;
+    require_arbiter(
+        &ctx.accounts.config,
+        &ctx.accounts.arbiter.key(),
+        ctx.accounts.arbiter_membership.as_deref(),
+        listing,
+    )?;
+
+    match outcome {
+        DisputeOutcome::Refund => {
+            move_lamports(
+                &listing.to_account_info(),
+                &ctx.accounts.buyer,
+                access.price_paid,
+            )?;
+            listing.clear_escrow();
+            if let Some(reputation) = ctx.accounts.reputation.as_mut() {
+                reputation.disputes += 1;
+            }
+            access.close(ctx.accounts.buyer.to_account_info())?;
+        }
+        DisputeOutcome::Release => {
+            release_escrow_lamports(
+                listing,
+                access.price_paid,
+                &ctx.accounts.config,
+                &ctx.accounts.seller,
+                ctx.accounts.fee_recipient.as_deref(),
+                ctx.remaining_accounts,
+            )?;
+            listing.complete_sale(access);
+        }
+    }
+
+    emit!(DisputeResolved {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        buyer: access.buyer,
+        arbiter: ctx.accounts.arbiter.key(),
+        outcome,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ResolveDisputeSpl<'info> {
+    #[account(
+        mut,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump,
+        constraint = listing.buyer == Some(access.buyer) @ IntelMarketError::NoPendingPurchase,
+        constraint = listing.payment_mint == Some(payment_mint.key()) @ IntelMarketError::PaymentMintMismatch
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ReportAccess::SEED, listing.intel_report.as_ref(), access.buyer.as_ref()],
+        bump = access.bump
+    )]
+    pub access: Account<'info, ReportAccess>,
+    /// CHECK: receives the access record's rent on refund, must match the disputing buyer
+    #[account(mut, address = access.buyer)]
+    pub buyer: UncheckedAccount<'info>,
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = listing
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = access.buyer
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = listing.seller
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+    // Counts a refund against the seller when their reputation account exists
+    #[account(
+        mut,
+        seeds = [AuthorReputation::SEED, listing.seller.as_ref()],
+        bump = reputation.bump
+    )]
+    pub reputation: Option<Account<'info, AuthorReputation>>,
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    // The fee recipient's associated token account, required when a fee is due
+    #[account(mut)]
+    pub fee_token: Option<Account<'info, TokenAccount>>,
+    pub arbiter_membership: Option<Account<'info, TeamMembership>>,
+    pub arbiter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn resolve_dispute_spl<'info>(
+    ctx: Context<'_, '_, '_, 'info, ResolveDisputeSpl<'info>>,
+    outcome: DisputeOutcome,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let access = &mut ctx.accounts.access;
+    listing.require_open_dispute()?;
+    require_arbiter(
+        &ctx.accounts.config,
+        &ctx.accounts.arbiter.key(),
+        ctx.accounts.arbiter_membership.as_deref(),
+        listing,
+    )?;
+
+    match outcome {
+        DisputeOutcome::Refund => {
+            transfer_from_escrow(
+                listing,
+                &ctx.accounts.payment_mint,
+                &ctx.accounts.escrow,
+                ctx.accounts.buyer_token.to_account_info(),
+                access.price_paid,
+                &ctx.accounts.token_program,
+            )?;
+            listing.clear_escrow();
+            if let Some(reputation) = ctx.accounts.reputation.as_mut() {
+                reputation.disputes += 1;
+            }
+            access.close(ctx.accounts.buyer.to_account_info())?;
+        }
+        DisputeOutcome::Release => {
+            release_escrow_tokens(
+                listing,
+                access.price_paid,
+                &ctx.accounts.config,
+                &ctx.accounts.payment_mint,
+                &ctx.accounts.escrow,
+                &ctx.accounts.seller_token.to_account_info(),
+                ctx.accounts
+                    .fee_token
+                    .as_ref()
+                    .map(|fee| fee.to_account_info()),
+                ctx.remaining_accounts,
+                &ctx.accounts.token_program,
+            )?;
+            listing.complete_sale(access);
+        }
+    }
+
+    emit!(DisputeResolved {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        buyer: access.buyer,
+        arbiter: ctx.accounts.arbiter.key(),
+        outcome,
+    });
+    Ok(())
+}
+
+// Arbiters must be designated in the config and cannot be a party to the purchase
+pub fn require_arbiter(
+    config: &ProgramConfig,
+    arbiter: &Pubkey,
+    membership: Option<&TeamMembership>,
+    listing: &Listing,
+) -> Result<()> {
+    require!(
+        config.is_arbiter(arbiter, membership),
+        IntelMarketError::NotArbiter
+    );
+    require!(
+        *arbiter != listing.seller && Some(*arbiter) != listing.buyer,
+        IntelMarketError::NotArbiter
+    );
+    Ok(())
+}
+
+// Closes the listing. A pending buyer is refunded and their access record closed,
+// so the buyer and access accounts are required while a payment is in escrow.
+// SPL listings also pass their escrow token account, which is closed to the seller,
+// plus the buyer's token account while a payment is pending. Tokens in the escrow beyond
+// the pending payment (sent to it directly) go to the seller's token account, which is
+// required in that case since a non-empty token account cannot be closed.
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller,
+        seeds = [Listing::SEED, listing.intel_report.as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// CHECK: refund destination, checked against the listing's pending buyer
+    #[account(mut)]
+    pub buyer: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub access: Option<Account<'info, ReportAccess>>,
+    pub payment_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub escrow: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub buyer_token: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller_token: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let listing = &accounts.listing;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        Listing::SEED,
+        listing.intel_report.as_ref(),
+        &[listing.bump],
+    ]];
+
+    let mut refunded = None;
+    let mut refunded_tokens = 0;
+    if let Some(pending) = listing.buyer {
+        let buyer = accounts
+            .buyer
+            .as_ref()
+            .ok_or(IntelMarketError::BuyerAccountMissing)?;
+        let access = accounts
+            .access
+            .as_ref()
+            .ok_or(IntelMarketError::BuyerAccountMissing)?;
+        require!(
+            buyer.key() == pending
+                && access.buyer == pending
+                && access.intel_report == listing.intel_report,
+            IntelMarketError::BuyerAccountMissing
+        );
+
+        match listing.payment_mint {
+            None => {
+                move_lamports(&listing.to_account_info(), buyer, access.price_paid)?;
+            }
+            Some(mint) => {
+                let (payment_mint, escrow, token_program) = spl_escrow_accounts(accounts, mint)?;
+                let buyer_token = accounts
+                    .buyer_token
+                    .as_ref()
+                    .ok_or(IntelMarketError::TokenAccountsMissing)?;
+                require!(
+                    buyer_token.key() == get_associated_token_address(&pending, &mint),
+                    IntelMarketError::PaymentMintMismatch
+                );
+                transfer_from_escrow(
+                    listing,
+                    payment_mint,
+                    escrow,
+                    buyer_token.to_account_info(),
+                    access.price_paid,
+                    token_program,
+                )?;
+                refunded_tokens = access.price_paid;
+            }
+        }
+        access.close(buyer.to_account_info())?;
+        refunded = Some(pending);
+    }
+
+    if let Some(mint) = listing.payment_mint {
+        let (payment_mint, escrow, token_program) = spl_escrow_accounts(accounts, mint)?;
+        // `escrow` was loaded before the refund, so its amount still includes it
+        let leftover = escrow.amount - refunded_tokens;
+        if leftover > 0 {
+            let seller_token = accounts
+                .seller_token
+                .as_ref()
+                .ok_or(IntelMarketError::TokenAccountsMissing)?;
+            require!(
+                seller_token.key() == get_associated_token_address(&listing.seller, &mint),
+                IntelMarketError::PaymentMintMismatch
+            );
+            transfer_from_escrow(
+                listing,
+                payment_mint,
+                escrow,
+                seller_token.to_account_info(),
+                leftover,
+                token_program,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: escrow.to_account_info(),
+                destination: accounts.seller.to_account_info(),
+                authority: listing.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    emit!(ListingCancelled {
+        listing: listing.key(),
+        intel_report: listing.intel_report,
+        refunded_buyer: refunded,
+    });
+    Ok(())
+}
+
+// Checks the optional token accounts of CancelListing against an SPL listing
+fn spl_escrow_accounts<'a, 'info>(
+    accounts: &'a CancelListing<'info>,
+    mint: Pubkey,
+) -> Result<(
+    &'a Account<'info, Mint>,
+    &'a Account<'info, TokenAccount>,
+    &'a Program<'info, Token>,
+)> {
+    let payment_mint = accounts
+        .payment_mint
+        .as_ref()
+        .ok_or(IntelMarketError::TokenAccountsMissing)?;
+    let escrow = accounts
+        .escrow
+        .as_ref()
+        .ok_or(IntelMarketError::TokenAccountsMissing)?;
+    let token_program = accounts
+        .token_program
+        .as_ref()
+        .ok_or(IntelMarketError::TokenAccountsMissing)?;
+    require!(
+        payment_mint.key() == mint,
+        IntelMarketError::PaymentMintMismatch
+    );
+    require!(
+        escrow.key() == get_associated_token_address(&accounts.listing.key(), &mint),
+        IntelMarketError::PaymentMintMismatch
+    );
+    Ok((payment_mint, escrow, token_program))
+}
+
+#[event]
+pub struct ReportListed {
+    pub listing: Pubkey,
+    pub intel_report: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct ReportPurchased {
+    pub listing: Pubkey,
+    pub intel_report: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct PurchaseSettled {
+    pub listing: Pubkey,
+    pub intel_report: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64, // Gross amount released from escrow
+    pub fee: u64,    // Protocol fee taken from `amount`
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub listing: Pubkey,
+    pub intel_report: Pubkey,
+    pub buyer: Pubkey,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub listing: Pubkey,
+    pub intel_report: Pubkey,
+    pub buyer: Pubkey,
+    pub arbiter: Pubkey,
+    pub outcome: DisputeOutcome,
+}
+
+#[event]
+pub struct ListingCancelled {
+    pub listing: Pubkey,
+    pub intel_report: Pubkey,
+    pub refunded_buyer: Option<Pubkey>,
+}
+
+// Source reputation: buyers whose purchase has settled may rate the report once, and
+// ratings are aggregated per author so incoming intel can be ranked by source quality.
+
+// Per-author reputation PDA, seeds = ["reputation", author]
+#[account]
+pub struct AuthorReputation {
+    pub author: Pubkey,
+    pub rating_count: u64, // Number of ratings received
+    pub score_sum: u64,    // Sum of all rating scores (1-5 each)
+    pub disputes: u64,     // Purchases of this author's reports refunded after a dispute
+    pub bump: u8,
+}
+
+impl AuthorReputation {
+    pub const SEED: &'static [u8] = b"reputation";
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 1;
+}
+
+// One rating per verified purchaser, seeds = ["rating", intel_report, rater]
+#[account]
+pub struct ReportRating {
+    pub intel_report: Pubkey,
+    pub rater: Pubkey,
+    pub score: u8,
+    pub rated_at: i64,
+    pub bump: u8,
+}
+
+impl ReportRating {
+    pub const SEED: &'static [u8] = b"rating";
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 1;
+    pub const MIN_SCORE: u8 = 1;
+    pub const MAX_SCORE: u8 = 5;
+}
+
+// Permissionless: anyone may pay to create an author's reputation account
+#[derive(Accounts)]
+#[instruction(author: Pubkey)]
+pub struct InitializeReputation<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = AuthorReputation::SPACE,
+        seeds = [AuthorReputation::SEED, author.as_ref()],
+        bump
+    )]
+    pub reputation: Account<'info, AuthorReputation>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_reputation(ctx: Context<InitializeReputation>, author: Pubkey) -> Result<()> {
+    let reputation = &mut ctx.accounts.reputation;
+    reputation.author = author;
+    reputation.rating_count = 0;
+    reputation.score_sum = 0;
+    reputation.disputes = 0;
+    reputation.bump = ctx.bumps.reputation;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RateIntelReport<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    #[account(
+        seeds = [ReportAccess::SEED, intel_report.key().as_ref(), rater.key().as_ref()],
+        bump = access.bump,
+        constraint = access.status == AccessStatus::Granted @ IntelMarketError::AccessNotGranted
+    )]
+    pub access: Account<'info, ReportAccess>,
+    #[account(
+        init,
+        payer = rater,
+        space = ReportRating::SPACE,
+        seeds = [ReportRating::SEED, intel_report.key().as_ref(), rater.key().as_ref()],
+        bump
+    )]
+    pub rating: Account<'info, ReportRating>,
+    #[account(
+        mut,
+        seeds = [AuthorReputation::SEED, intel_report.author.as_ref()],
+        bump = reputation.bump
+    )]
+    pub reputation: Account<'info, AuthorReputation>,
+    #[account(mut)]
+    pub rater: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn rate_intel_report(ctx: Context<RateIntelReport>, score: u8) -> Result<()> {
+    require!(
+        (ReportRating::MIN_SCORE..=ReportRating::MAX_SCORE).contains(&score),
+        IntelMarketError::InvalidRating
+    );
+    let rating = &mut ctx.accounts.rating;
+    rating.intel_report = ctx.accounts.intel_report.key();
+    rating.rater = *ctx.accounts.rater.key;
+    rating.score = score;
+    rating.rated_at = Clock::get()?.unix_timestamp;
+    rating.bump = ctx.bumps.rating;
+
+    let reputation = &mut ctx.accounts.reputation;
+    reputation.rating_count += 1;
+    reputation.score_sum += score as u64;
+
+    emit!(ReportRated {
+        intel_report: rating.intel_report,
+        author: reputation.author,
+        rater: rating.rater,
+        score,
+    });
+    Ok(())
+}
+
+#[event]
+pub struct ReportRated {
+    pub intel_report: Pubkey,
+    pub author: Pubkey,
+    pub rater: Pubkey,
+    pub score: u8,
+}
+
+// Example (off-chain): rank authors by average score
+//   const reps = await program.account.authorReputation.all();
+//   reps.sort((a, b) => b.account.scoreSum / b.account.ratingCount - a.account.scoreSum / a.account.ratingCount);
+
+// Evidence attachments: hashes of collected artefacts (logs, captures, images, ...) linked
+// to the report they support, mirroring the TS `Evidence` model. The artefact itself stays
+// off-chain; the account fixes its digest and records who holds it. Custody only moves when
+// both the current and the next custodian sign, and every hand-off is emitted as an event,
+// so the chain of custody can be replayed from the transaction history.
+
+// Evidence PDA, seeds = ["evidence", intel_report, content_hash]
+#[account]
+pub struct Evidence {
+    pub intel_report: Pubkey,   // Report this evidence supports
+    pub content_hash: [u8; 32], // SHA-256 digest of the artefact
+    pub media_type: String,     // MIME type, e.g. "application/vnd.tcpdump.pcap"
+    pub collected_by: Pubkey,   // Wallet that attached the evidence
+    pub collected_at: i64,      // On-chain clock
+    pub custodian: Pubkey,      // Current holder of the artefact
+    pub custody_transfers: u32, // Number of hand-offs so far
+    pub last_transfer_at: i64,  // On-chain clock, `collected_at` until the first hand-off
+    pub bump: u8,
+}
+
+impl Evidence {
+    pub const SEED: &'static [u8] = b"evidence";
+    pub const MAX_MEDIA_TYPE_LEN: usize = 64;
+    pub const SPACE: usize = 8 + 32 + 32 + (4 + Self::MAX_MEDIA_TYPE_LEN) + 32 + 8 + 32 + 4 + 8 + 1;
+    pub const REPORT_OFFSET: usize = 8;
+}
+
+// The report's author may attach evidence; on team reports, so may its admins and analysts
+#[derive(Accounts)]
+#[instruction(content_hash: [u8; 32])]
+pub struct AttachEvidence<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        constraint = intel_report.version == IntelReport::VERSION @ IntelMarketError::UnsupportedAccountVersion
+    )]
+    pub intel_report: Account<'info, IntelReport>,
+    // Required when the collector is not the report's author
+    pub team_membership: Option<Account<'info, TeamMembership>>,
+    #[account(
+        init,
+        payer = collector,
+        space = Evidence::SPACE,
+        seeds = [Evidence::SEED, intel_report.key().as_ref(), content_hash.as_ref()],
+        bump
+    )]
+    pub evidence: Account<'info, Evidence>,
+    #[account(mut)]
+    pub collector: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn attach_evidence(
+    ctx: Context<AttachEvidence>,
+    content_hash: [u8; 32],
+    media_type: String,
+) -> Result<()> {
+    require!(
+        media_type.len() <= Evidence::MAX_MEDIA_TYPE_LEN,
+        IntelMarketError::MediaTypeTooLong
+    );
+    let report = &ctx.accounts.intel_report;
+    let collector = ctx.accounts.collector.key();
+    if collector != report.author {
+        let team = report.team.ok_or(IntelMarketError::Unauthorized)?;
+        let membership = ctx
+            .accounts
+            .team_membership
+            .as_ref()
+            .ok_or(IntelMarketError::NotTeamMember)?;
+        require_voting_member(&team, membership, &collector)?;
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let evidence = &mut ctx.accounts.evidence;
+    evidence.intel_report = report.key();
+    evidence.content_hash = content_hash;
+    evidence.media_type = media_type;
+    evidence.collected_by = collector;
+    evidence.collected_at = now;
+    evidence.custodian = collector;
+    evidence.custody_transfers = 0;
+    evidence.last_transfer_at = now;
+    evidence.bump = ctx.bumps.evidence;
+
+    emit!(EvidenceAttached {
+        evidence: evidence.key(),
+        intel_report: evidence.intel_report,
+        content_hash,
+        custodian: collector,
+        collected_at: now,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct TransferCustody<'info> {
+    #[account(
+        seeds = [ProgramConfig::SEED],
+        bump = config.bump,
+        constraint = !config.paused @ IntelMarketError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [Evidence::SEED, evidence.intel_report.as_ref(), evidence.content_hash.as_ref()],
+        bump = evidence.bump,
+        constraint = evidence.custodian == custodian.key() @ IntelMarketError::NotCustodian
+    )]
+    pub evidence: Account<'info, Evidence>,
+    pub custodian: Signer<'info>,
+    pub new_custodian: Signer<'info>,
+}
+
+pub fn transfer_custody(ctx: Context<TransferCustody>) -> Result<()> {
+    let new_custodian = ctx.accounts.new_custodian.key();
+    let evidence = &mut ctx.accounts.evidence;
+    require!(
+        new_custodian != evidence.custodian,
+        IntelMarketError::NotCustodian
+    );
+    let now = Clock::get()?.unix_timestamp;
+    let previous = evidence.custodian;
+    evidence.custodian = new_custodian;
+    evidence.custody_transfers += 1;
+    evidence.last_transfer_at = now;
+
+    emit!(EvidenceCustodyTransferred {
+        evidence: evidence.key(),
+        from: previous,
+        to: new_custodian,
+        transfer: evidence.custody_transfers,
+        transferred_at: now,
+    });
+    Ok(())
+}
+
+#[event]
+pub struct EvidenceAttached {
+    pub evidence: Pubkey,
+    pub intel_report: Pubkey,
+    pub content_hash: [u8; 32],
+    pub custodian: Pubkey,
+    pub collected_at: i64,
+}
+
+#[event]
+pub struct EvidenceCustodyTransferred {
+    pub evidence: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub transfer: u32, // 1 for the first hand-off
+    pub transferred_at: i64,
+}
+
+// Example (off-chain): all evidence for a report
+//   const evidence = await program.account.evidence.all([
+//     { memcmp: { offset: 8, bytes: report.toBase58() } }, // Evidence::REPORT_OFFSET
+//   ]);
+// Verifying an artefact: sha256(file) must equal `contentHash`, and the custody history is
+// the report's EvidenceCustodyTransferred events in order.
+
+// Client-side sealing and opening of encrypted report bodies, for the relay node and CLI.
+// Member wallets are Ed25519 keys; they are converted to X25519 for key agreement. Each
+// report gets a fresh ephemeral X25519 key, and each envelope wraps the content key under
+// SHA-256(domain || shared secret || ephemeral key || recipient key).
+#[cfg(feature = "client")]
+pub mod client {
+    use super::{KeyEnvelope, ReportContent};
+    use anchor_lang::prelude::Pubkey;
+    use chacha20poly1305::aead::{Aead, NewAead};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce, XChaCha20Poly1305, XNonce};
+    use curve25519_dalek::constants::X25519_BASEPOINT;
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use curve25519_dalek::montgomery::MontgomeryPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use rand_core::{CryptoRng, RngCore};
+    use sha2::{Digest, Sha256, Sha512};
+
+    const ENVELOPE_DOMAIN: &[u8] = b"intel_report:envelope:v1";
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ClientError {
+        InvalidRecipient(Pubkey), // Not a valid Ed25519 point
+        TooManyRecipients,
+        NotEncrypted,
+        NoEnvelope, // The report was not sealed to this member
+        EncryptionFailed,
+        DecryptionFailed,
+    }
+
+    // Ed25519 public key -> X25519 public key (birational map to the Montgomery form)
+    pub fn x25519_public(member: &Pubkey) -> Option<MontgomeryPoint> {
+        CompressedEdwardsY(member.to_bytes())
+            .decompress()
+            .map(|point| point.to_montgomery())
+    }
+
+    // Ed25519 secret seed (first half of a Solana keypair) -> X25519 secret scalar
+    pub fn x25519_secret(seed: &[u8; 32]) -> Scalar {
+        let hash = Sha512::digest(seed);
+        let mut bits = [0u8; 32];
+        bits.copy_from_slice(&hash[..32]);
+        clamp(bits)
+    }
+
+    fn clamp(mut bits: [u8; 32]) -> Scalar {
+        bits[0] &= 248;
+        bits[31] &= 127;
+        bits[31] |= 64;
+        Scalar::from_bits(bits)
+    }
+
+    fn wrapping_key(
+        shared: &MontgomeryPoint,
+        ephemeral: &MontgomeryPoint,
+        recipient: &MontgomeryPoint,
+    ) -> Result<Key, ClientError> {
+        // An all-zero secret means a low-order point was supplied
+        if shared.as_bytes() == &[0u8; 32] {
+            return Err(ClientError::DecryptionFailed);
+        }
+        let digest = Sha256::new()
+            .chain_update(ENVELOPE_DOMAIN)
+            .chain_update(shared.as_bytes())
+            .chain_update(ephemeral.as_bytes())
+            .chain_update(recipient.as_bytes())
+            .finalize();
+        Ok(digest)
+    }
+
+    // Every wrapping key is unique to (ephemeral key, recipient), so a fixed nonce is safe
+    fn envelope_cipher(key: &Key) -> (ChaCha20Poly1305, Nonce) {
+        (ChaCha20Poly1305::new(key), Nonce::default())
+    }
+
+    // Seals `plaintext` so that only `recipients` (normally every member of the report's
+    // team, listed from TeamMembership accounts) can open it.
+    pub fn seal_report<R: RngCore + CryptoRng>(
+        plaintext: &[u8],
+        recipients: &[Pubkey],
+        rng: &mut R,
+    ) -> Result<ReportContent, ClientError> {
+        if recipients.len() > ReportContent::MAX_ENVELOPES {
+            return Err(ClientError::TooManyRecipients);
+        }
+        let mut content_key = [0u8; 32];
+        let mut nonce = [0u8; 24];
+        let mut ephemeral_bits = [0u8; 32];
+        rng.fill_bytes(&mut content_key);
+        rng.fill_bytes(&mut nonce);
+        rng.fill_bytes(&mut ephemeral_bits);
+
+        let ciphertext = XChaCha20Poly1305::new(&Key::from(content_key))
+            .encrypt(&XNonce::from(nonce), plaintext)
+            .map_err(|_| ClientError::EncryptionFailed)?;
+
+        let ephemeral_secret = clamp(ephemeral_bits);
+        let ephemeral_key = X25519_BASEPOINT * ephemeral_secret;
+        let mut envelopes = Vec::with_capacity(recipients.len());
+        for recipient in recipients {
+            if envelopes
+                .iter()
+                .any(|e: &KeyEnvelope| e.recipient == *recipient)
+            {
+                continue;
+            }
+            let point =
+                x25519_public(recipient).ok_or(ClientError::InvalidRecipient(*recipient))?;
+            let key = wrapping_key(&(point * ephemeral_secret), &ephemeral_key, &point)
+                .map_err(|_| ClientError::InvalidRecipient(*recipient))?;
+            let (cipher, envelope_nonce) = envelope_cipher(&key);
+            let sealed = cipher
+                .encrypt(&envelope_nonce, content_key.as_ref())
+                .map_err(|_| ClientError::EncryptionFailed)?;
+            let mut sealed_key = [0u8; 48];
+            sealed_key.copy_from_slice(&sealed);
+            envelopes.push(KeyEnvelope {
+                recipient: *recipient,
+                sealed_key,
+            });
+        }
+
+        Ok(ReportContent::Encrypted {
+            ephemeral_key: ephemeral_key.to_bytes(),
+            nonce,
+            ciphertext,
+            envelopes,
+        })
+    }
+
+    // Opens an encrypted report as `member`, given the member's Ed25519 secret seed
+    pub fn open_report(
+        content: &ReportContent,
+        member: &Pubkey,
+        seed: &[u8; 32],
+    ) -> Result<Vec<u8>, ClientError> {
+        let ReportContent::Encrypted {
+            ephemeral_key,
+            nonce,
+            ciphertext,
+            envelopes,
+        } = content
+        else {
+            return Err(ClientError::NotEncrypted);
+        };
+        let envelope = envelopes
+            .iter()
+            .find(|e| e.recipient == *member)
+            .ok_or(ClientError::NoEnvelope)?;
+
+        let ephemeral_key = MontgomeryPoint(*ephemeral_key);
+        let recipient = x25519_public(member).ok_or(ClientError::InvalidRecipient(*member))?;
+        let key = wrapping_key(
+            &(ephemeral_key * x25519_secret(seed)),
+            &ephemeral_key,
+            &recipient,
+        )?;
+        let (cipher, envelope_nonce) = envelope_cipher(&key);
+        let content_key: [u8; 32] = cipher
+            .decrypt(&envelope_nonce, envelope.sealed_key.as_ref())
+            .ok()
+            .and_then(|key| key.try_into().ok())
+            .ok_or(ClientError::DecryptionFailed)?;
+
+        XChaCha20Poly1305::new(&Key::from(content_key))
+            .decrypt(&XNonce::from(*nonce), ciphertext.as_slice())
+            .map_err(|_| ClientError::DecryptionFailed)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+        use rand_core::OsRng;
+
+        // Wallet public key for an Ed25519 secret seed, as a Solana keypair would derive it
+        fn wallet(seed: [u8; 32]) -> ([u8; 32], Pubkey) {
+            let point = x25519_secret(&seed) * ED25519_BASEPOINT_POINT;
+            (seed, Pubkey::new_from_array(point.compress().to_bytes()))
+        }
+
+        #[test]
+        fn members_open_what_was_sealed_to_them() {
+            let (alice_seed, alice) = wallet([1; 32]);
+            let (bob_seed, bob) = wallet([2; 32]);
+            let (eve_seed, eve) = wallet([3; 32]);
+            let body = b"Beacon traffic to 203.0.113.7 every 300s";
+
+            let content = seal_report(body, &[alice, bob], &mut OsRng).unwrap();
+            assert_eq!(open_report(&content, &alice, &alice_seed).unwrap(), body);
+            assert_eq!(open_report(&content, &bob, &bob_seed).unwrap(), body);
+            assert_eq!(
+                open_report(&content, &eve, &eve_seed),
+                Err(ClientError::NoEnvelope)
+            );
+            // A non-member cannot use someone else's envelope either
+            assert_eq!(
+                open_report(&content, &alice, &eve_seed),
+                Err(ClientError::DecryptionFailed)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1_report() -> IntelReportV1 {
+        IntelReportV1 {
+            title: "C2 beacon".to_string(),
+            content: "Beacon every 300s".to_string(),
+            tags: vec![
+                "sigint".to_string(),
+                "Hum-Int".to_string(),
+                "unregistered".to_string(),
+                "SIGINT".to_string(),
+            ],
+            latitude: 48.856_613,
+            longitude: 2.352_222_4,
+            timestamp: 1_700_000_000,
+            author: Pubkey::new_unique(),
+        }
+    }
+
+    // Account data as the first deployment allocated and wrote it
+    fn v1_account<T: AnchorSerialize>(discriminator: [u8; 8], v1: &T, space: usize) -> Vec<u8> {
+        let mut data = discriminator.to_vec();
+        v1.serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        data
+    }
+
+    fn tag(id: u16, name: &str) -> Tag {
+        Tag {
+            id,
+            name: name.to_string(),
+            report_count: 0,
+            created_by: Pubkey::new_unique(),
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn v1_report_upgrades_to_the_current_layout() {
+        let v1 = v1_report();
+        let data = v1_account(IntelReport::DISCRIMINATOR, &v1, IntelReportV1::SPACE);
+        let ReportLayout::V1(loaded) = IntelReport::load_versioned(&data).unwrap() else {
+            panic!("v1 report not recognized");
+        };
+        assert_eq!(loaded.title, v1.title);
+
+        let registered = [tag(0, "SIGINT"), tag(3, "HUMINT")];
+        let report = loaded.upgrade(&registered, 1_700_000_900).unwrap();
+        assert_eq!(report.version, IntelReport::VERSION);
+        assert_eq!((report.latitude, report.longitude), (48_856_613, 2_352_222));
+        assert_eq!(report.location_precision, LocationPrecision::Exact);
+        assert_eq!(report.geohash, geohash_encode(48_856_613, 2_352_222));
+        assert_eq!(report.tags, vec![0, 3]);
+        assert_eq!(report.content, ReportContent::Inline(v1.content));
+        assert_eq!(report.observed_at, v1.timestamp);
+        assert_eq!(report.created_at, 1_700_000_900);
+        assert_eq!(report.author, v1.author);
+        assert_eq!(report.revision, 1);
+        assert!(!report.geo_indexed);
+
+        // What migrate_intel_report writes back is a regular current-layout account
+        let mut migrated = Vec::new();
+        report.try_serialize(&mut migrated).unwrap();
+        assert!(migrated.len() <= IntelReport::space(&report.title, &report.content, &report.tags));
+        assert_eq!(
+            &migrated[IntelReport::GEOHASH_OFFSET..][..8],
+            &report.geohash
+        );
+        assert!(matches!(
+            IntelReport::load_versioned(&migrated).unwrap(),
+            ReportLayout::Current(_)
+        ));
+    }
+
+    #[test]
+    fn v1_coordinates_must_be_positions_on_earth() {
+        for (latitude, longitude, error) in [
+            (f64::NAN, 0.0, IntelMarketError::LatitudeOutOfRange),
+            (90.000_001, 0.0, IntelMarketError::LatitudeOutOfRange),
+            (0.0, f64::INFINITY, IntelMarketError::LongitudeOutOfRange),
+            (0.0, -180.000_001, IntelMarketError::LongitudeOutOfRange),
+        ] {
+            let v1 = IntelReportV1 {
+                latitude,
+                longitude,
+                ..v1_report()
+            };
+            assert_eq!(v1.upgrade(&[], 0).map(|_| ()).unwrap_err(), error.into());
+        }
+        // Values within half a microdegree of the limits round onto them
+        let v1 = IntelReportV1 {
+            latitude: -90.000_000_4,
+            longitude: 180.000_000_4,
+            ..v1_report()
+        };
+        let report = v1.upgrade(&[], 0).unwrap();
+        assert_eq!(
+            (report.latitude, report.longitude),
+            (-IntelReport::MAX_LATITUDE, IntelReport::MAX_LONGITUDE)
+        );
+    }
+
+    #[test]
+    fn report_layouts_are_recognized_positively() {
+        let v1 = IntelReportV1 {
+            title: String::new(),
+            tags: Vec::new(),
+            ..v1_report()
+        };
+        let data = v1_account(IntelReport::DISCRIMINATOR, &v1, IntelReportV1::SPACE);
+        assert!(matches!(
+            IntelReport::load_versioned(&data).unwrap(),
+            ReportLayout::V1(_)
+        ));
+
+        // A v1 layout at any other size is not taken for one
+        let data = v1_account(IntelReport::DISCRIMINATOR, &v1_report(), 512);
+        assert_eq!(
+            IntelReport::load_versioned(&data).map(|_| ()).unwrap_err(),
+            IntelMarketError::UnsupportedAccountVersion.into()
+        );
+    }
+
+    #[test]
+    fn team_layouts_are_told_apart_by_size() {
+        let v1 = CyberTeamV1 {
+            name: "Case room".to_string(),
+            members: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let data = v1_account(CyberTeam::DISCRIMINATOR, &v1, CyberTeamV1::SPACE);
+        let TeamLayout::V1(loaded) = CyberTeam::load_versioned(&data).unwrap() else {
+            panic!("v1 team not recognized");
+        };
+        assert_eq!(loaded.name, v1.name);
+        assert_eq!(loaded.members, v1.members);
+
+        // At the current size only the current version byte is accepted
+        let team = CyberTeam {
+            version: CyberTeam::VERSION,
+            authority: v1.members[0],
+            name: v1.name,
+            member_count: 2,
+            voter_count: 2,
+            open: false,
+            threshold: 1,
+            proposal_count: 0,
+        };
+        let mut data = Vec::new();
+        team.try_serialize(&mut data).unwrap();
+        data.resize(CyberTeam::SPACE, 0);
+        assert!(matches!(
+            CyberTeam::load_versioned(&data).unwrap(),
+            TeamLayout::Current(_)
+        ));
+        data[8] = 1;
+        assert_eq!(
+            CyberTeam::load_versioned(&data).map(|_| ()).unwrap_err(),
+            IntelMarketError::UnsupportedAccountVersion.into()
+        );
+    }
+
+    #[test]
+    fn voters_never_drop_below_the_threshold() {
+        let mut team = CyberTeam {
+            version: CyberTeam::VERSION,
+            authority: Pubkey::new_unique(),
+            name: "Case room".to_string(),
+            member_count: 1,
+            voter_count: 1,
+            open: true,
+            threshold: 1,
+            proposal_count: 0,
+        };
+        team.record_role_change(None, Some(TeamRole::Observer))
+            .unwrap();
+        team.record_role_change(None, Some(TeamRole::Analyst))
+            .unwrap();
+        team.record_role_change(None, Some(TeamRole::Admin))
+            .unwrap();
+        assert_eq!(team.voter_count, 3);
+        team.threshold = 2;
+
+        team.record_role_change(Some(TeamRole::Analyst), Some(TeamRole::Admin))
+            .unwrap();
+        team.record_role_change(Some(TeamRole::Observer), None)
+            .unwrap();
+        team.record_role_change(Some(TeamRole::Admin), Some(TeamRole::Observer))
+            .unwrap();
+        assert_eq!(team.voter_count, 2);
+        assert_eq!(
+            team.record_role_change(Some(TeamRole::Analyst), None)
+                .unwrap_err(),
+            IntelMarketError::ThresholdExceedsVoters.into()
+        );
+        assert_eq!(
+            team.record_role_change(Some(TeamRole::Admin), Some(TeamRole::Observer))
+                .unwrap_err(),
+            IntelMarketError::ThresholdExceedsVoters.into()
+        );
+        assert_eq!(team.voter_count, 2);
+    }
+
+    #[test]
+    fn largest_report_fits_one_allocation() {
+        let limit = ProgramConfig::MAX_CONTENT_LEN_LIMIT as usize;
+        let content = ReportContent::Encrypted {
+            ephemeral_key: [0; 32],
+            nonce: [0; 24],
+            ciphertext: vec![0; limit + ReportContent::AEAD_TAG_LEN],
+            envelopes: (0..ReportContent::MAX_ENVELOPES)
+                .map(|_| KeyEnvelope {
+                    recipient: Pubkey::new_unique(),
+                    sealed_key: [0; 48],
+                })
+                .collect(),
+        };
+        let title = "t".repeat(IntelReport::MAX_TITLE_LEN);
+        let tags: Vec<u16> = (0..IntelReport::MAX_TAGS as u16).collect();
+        assert_eq!(
+            IntelReport::allocation_space(&title, &content, &tags).unwrap(),
+            MAX_PERMITTED_DATA_INCREASE
+        );
+
+        let mut config = config(vec![], None);
+        config.max_content_len = limit as u32;
+        validate_report_fields(
+            &config,
+            &title,
+            &content,
+            &tags,
+            0,
+            0,
+            LocationPrecision::Exact,
+        )
+        .unwrap();
+        let inline = ReportContent::Inline("b".repeat(limit));
+        validate_report_fields(
+            &config,
+            &title,
+            &inline,
+            &tags,
+            0,
+            0,
+            LocationPrecision::Exact,
+        )
+        .unwrap();
+        assert!(IntelReport::space(&title, &inline, &tags) < MAX_PERMITTED_DATA_INCREASE);
+    }
+
+    #[test]
+    fn tag_spellings_share_a_canonical_name() {
+        for name in ["SIGINT", "sigint", "Sig-Int", " sig_int "] {
+            assert_eq!(canonical_tag_name(name), "SIGINT");
+        }
+        assert_eq!(canonical_tag_name("--"), "");
+    }
+
+    #[test]
+    fn geohash_matches_reference_encoding() {
+        assert_eq!(&geohash_encode(57_649_110, 10_407_440), b"u4pruydq");
+        assert_eq!(&geohash_encode(37_774_900, -122_419_400), b"9q8yyk8y");
+        assert_eq!(&geohash_encode(-90_000_000, -180_000_000), b"00000000");
+    }
+
+    #[test]
+    fn coarsening_rounds_to_the_precision_grid() {
+        let max = IntelReport::MAX_LONGITUDE;
+        assert_eq!(
+            LocationPrecision::Exact.coarsen(-122_419_412, max),
+            -122_419_412
+        );
+        assert_eq!(
+            LocationPrecision::Street.coarsen(-122_419_451, max),
+            -122_419_500
+        );
+        assert_eq!(LocationPrecision::City.coarsen(37_774_900, max), 37_770_000);
+        assert_eq!(
+            LocationPrecision::Country.coarsen(37_500_000, max),
+            38_000_000
+        );
+        assert_eq!(
+            LocationPrecision::Country.coarsen(-37_500_001, max),
+            -38_000_000
+        );
+        // Rounding never leaves the valid range
+        assert_eq!(LocationPrecision::Country.coarsen(179_999_999, max), max);
+        assert_eq!(
+            LocationPrecision::Country.coarsen(-89_600_000, IntelReport::MAX_LATITUDE),
+            -IntelReport::MAX_LATITUDE
+        );
+        // Whatever coarsen returns is accepted on-chain
+        for precision in [LocationPrecision::Street, LocationPrecision::Country] {
+            assert!(precision.is_on_grid(precision.coarsen(-122_419_451, max)));
+        }
+        assert!(!LocationPrecision::City.is_on_grid(37_774_900));
+        assert!(LocationPrecision::City.is_on_grid(-37_770_000));
+    }
+
+    const ESCROWED_AT: i64 = 1_700_000_000;
+    const PRICE: u64 = 1_000_000;
+
+    fn listing(seller: Pubkey) -> Listing {
+        Listing {
+            seller,
+            intel_report: Pubkey::new_unique(),
+            price: PRICE,
+            payment_mint: None,
+            buyer: None,
+            escrowed_at: 0,
+            sales: 0,
+            bump: 255,
+            dispute_deadline: None,
+            shares: Vec::new(),
+        }
+    }
+
+    fn access() -> ReportAccess {
+        ReportAccess {
+            intel_report: Pubkey::default(),
+            buyer: Pubkey::default(),
+            price_paid: 0,
+            status: AccessStatus::Pending,
+            purchased_at: 0,
+            bump: 0,
+        }
+    }
+
+    // A listing with `buyer`'s payment in escrow since ESCROWED_AT
+    fn purchased(seller: Pubkey, buyer: Pubkey) -> (Listing, ReportAccess) {
+        let mut listing = listing(seller);
+        let mut access = access();
+        listing
+            .record_purchase(&mut access, buyer, 254, ESCROWED_AT)
+            .unwrap();
+        (listing, access)
+    }
+
+    fn config(arbiters: Vec<Pubkey>, arbiter_team: Option<Pubkey>) -> ProgramConfig {
+        ProgramConfig {
+            admin: Pubkey::new_unique(),
+            fee_bps: 0,
+            fee_recipient: None,
+            bump: 255,
+            arbiters,
+            arbiter_team,
+            paused: false,
+            max_content_len: IntelReport::MAX_CONTENT_LEN as u32,
+            fees: FeeSchedule::default(),
+            min_report_interval: 0,
+            daily_report_cap: 0,
+        }
+    }
+
+    #[test]
+    fn report_rate_limits_apply_per_author() {
+        let mut config = config(Vec::new(), None);
+        config.min_report_interval = 60;
+        config.daily_report_cap = 3;
+        let mut state = AuthorState {
+            author: Pubkey::new_unique(),
+            report_count: 0,
+            bump: 255,
+            last_report_at: 0,
+            recent_reports: [0; AuthorState::MAX_DAILY_REPORT_CAP],
+        };
+
+        state.record_report(&config, ESCROWED_AT).unwrap();
+        assert_eq!(
+            state.record_report(&config, ESCROWED_AT + 59).unwrap_err(),
+            IntelMarketError::ReportRateLimited.into()
+        );
+        state.record_report(&config, ESCROWED_AT + 60).unwrap();
+        state.record_report(&config, ESCROWED_AT + 120).unwrap();
+        assert_eq!(
+            state.record_report(&config, ESCROWED_AT + 180).unwrap_err(),
+            IntelMarketError::DailyReportCapReached.into()
+        );
+        // The first report leaves the window a day after it was made
+        state
+            .record_report(&config, ESCROWED_AT + AuthorState::WINDOW_SECS)
+            .unwrap();
+        assert_eq!(
+            state
+                .record_report(&config, ESCROWED_AT + AuthorState::WINDOW_SECS + 59)
+                .unwrap_err(),
+            IntelMarketError::ReportRateLimited.into()
+        );
+    }
+
+    #[test]
+    fn daily_cap_holds_across_the_window_boundary() {
+        let mut config = config(Vec::new(), None);
+        config.daily_report_cap = 3;
+        let mut state = AuthorState {
+            author: Pubkey::new_unique(),
+            report_count: 0,
+            bump: 255,
+            last_report_at: 0,
+            recent_reports: [0; AuthorState::MAX_DAILY_REPORT_CAP],
+        };
+        let day = AuthorState::WINDOW_SECS;
+
+        // A burst just before a day boundary still counts just after it
+        for at in [day - 3, day - 2, day - 1] {
+            state.record_report(&config, ESCROWED_AT + at).unwrap();
+        }
+        for at in [day, day + 1, 2 * day - 4] {
+            assert_eq!(
+                state.record_report(&config, ESCROWED_AT + at).unwrap_err(),
+                IntelMarketError::DailyReportCapReached.into()
+            );
+        }
+        // Each report frees its slot exactly 24h after it was made
+        state
+            .record_report(&config, ESCROWED_AT + 2 * day - 3)
+            .unwrap();
+        assert_eq!(
+            state
+                .record_report(&config, ESCROWED_AT + 2 * day - 3)
+                .unwrap_err(),
+            IntelMarketError::DailyReportCapReached.into()
+        );
+        state
+            .record_report(&config, ESCROWED_AT + 2 * day - 2)
+            .unwrap();
+
+        // With the largest cap every slot is in use and the oldest is overwritten
+        config.daily_report_cap = AuthorState::MAX_DAILY_REPORT_CAP as u32;
+        let start = ESCROWED_AT + 10 * day;
+        for i in 0..AuthorState::MAX_DAILY_REPORT_CAP as i64 {
+            state.record_report(&config, start + i).unwrap();
+        }
+        assert_eq!(
+            state.record_report(&config, start + day - 1).unwrap_err(),
+            IntelMarketError::DailyReportCapReached.into()
+        );
+        state.record_report(&config, start + day).unwrap();
+    }
+
+    fn membership(team: Pubkey, member: Pubkey, role: TeamRole) -> TeamMembership {
+        TeamMembership {
+            team,
+            member,
+            role,
+            payer: member,
+            joined_at: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn purchase_holds_payment_in_escrow() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut listing, access) = purchased(seller, buyer);
+        assert_eq!(listing.buyer, Some(buyer));
+        assert_eq!(access.status, AccessStatus::Pending);
+        assert_eq!(access.price_paid, PRICE);
+
+        let mut second = self::access();
+        let err = listing
+            .record_purchase(&mut second, Pubkey::new_unique(), 1, ESCROWED_AT)
+            .unwrap_err();
+        assert_eq!(err, IntelMarketError::EscrowOccupied.into());
+    }
+
+    #[test]
+    fn seller_cannot_buy_own_listing() {
+        let seller = Pubkey::new_unique();
+        let mut listing = listing(seller);
+        let err = listing
+            .record_purchase(&mut access(), seller, 1, ESCROWED_AT)
+            .unwrap_err();
+        assert_eq!(err, IntelMarketError::Unauthorized.into());
+    }
+
+    #[test]
+    fn settlement_authority_respects_hold_period() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (listing, access) = purchased(seller, buyer);
+        let hold_end = ESCROWED_AT + Listing::ESCROW_HOLD_SECS;
+
+        assert!(listing
+            .check_settle_authority(&access, &buyer, ESCROWED_AT)
+            .is_ok());
+        assert_eq!(
+            listing
+                .check_settle_authority(&access, &seller, hold_end - 1)
+                .unwrap_err(),
+            IntelMarketError::EscrowHoldActive.into()
+        );
+        assert!(listing
+            .check_settle_authority(&access, &seller, hold_end)
+            .is_ok());
+        assert_eq!(
+            listing
+                .check_settle_authority(&access, &Pubkey::new_unique(), hold_end)
+                .unwrap_err(),
+            IntelMarketError::Unauthorized.into()
+        );
+    }
+
+    #[test]
+    fn dispute_freezes_seller_settlement_until_deadline() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut listing, access) = purchased(seller, buyer);
+        let opened_at = ESCROWED_AT + Listing::ESCROW_HOLD_SECS - 1;
+
+        let deadline = listing.open_dispute(&buyer, opened_at).unwrap();
+        assert_eq!(deadline, opened_at + Listing::DISPUTE_WINDOW_SECS);
+        assert!(listing.dispute_active(deadline - 1));
+        assert_eq!(
+            listing
+                .check_settle_authority(&access, &seller, deadline - 1)
+                .unwrap_err(),
+            IntelMarketError::DisputeActive.into()
+        );
+        // The buyer may still withdraw the dispute by confirming the purchase
+        assert!(listing
+            .check_settle_authority(&access, &buyer, deadline - 1)
+            .is_ok());
+        // An unresolved dispute lapses and normal settlement resumes
+        assert!(!listing.dispute_active(deadline));
+        assert!(listing
+            .check_settle_authority(&access, &seller, deadline)
+            .is_ok());
+    }
+
+    #[test]
+    fn dispute_requires_pending_buyer_within_hold_period() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut listing, _) = purchased(seller, buyer);
+
+        assert_eq!(
+            listing.open_dispute(&seller, ESCROWED_AT).unwrap_err(),
+            IntelMarketError::NoPendingPurchase.into()
+        );
+        assert_eq!(
+            listing
+                .open_dispute(&buyer, ESCROWED_AT + Listing::ESCROW_HOLD_SECS)
+                .unwrap_err(),
+            IntelMarketError::DisputeWindowClosed.into()
+        );
+        listing.open_dispute(&buyer, ESCROWED_AT).unwrap();
+        assert_eq!(
+            listing.open_dispute(&buyer, ESCROWED_AT + 1).unwrap_err(),
+            IntelMarketError::DisputeAlreadyOpened.into()
+        );
+    }
+
+    #[test]
+    fn resolution_requires_open_dispute() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut listing, _) = purchased(seller, buyer);
+        assert_eq!(
+            listing.require_open_dispute().unwrap_err(),
+            IntelMarketError::NoActiveDispute.into()
+        );
+        listing.open_dispute(&buyer, ESCROWED_AT).unwrap();
+        assert!(listing.require_open_dispute().is_ok());
+    }
+
+    #[test]
+    fn refund_clears_escrow_for_next_buyer() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut listing, _) = purchased(seller, buyer);
+        listing.open_dispute(&buyer, ESCROWED_AT).unwrap();
+
+        listing.clear_escrow();
+        assert_eq!(listing.buyer, None);
+        assert_eq!(listing.dispute_deadline, None);
+        assert_eq!(listing.sales, 0);
+        assert!(listing
+            .record_purchase(&mut access(), Pubkey::new_unique(), 1, ESCROWED_AT)
+            .is_ok());
+    }
+
+    #[test]
+    fn release_completes_sale_and_grants_access() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut listing, mut access) = purchased(seller, buyer);
+        listing.open_dispute(&buyer, ESCROWED_AT).unwrap();
+
+        listing.complete_sale(&mut access);
+        assert_eq!(listing.buyer, None);
+        assert_eq!(listing.dispute_deadline, None);
+        assert_eq!(listing.sales, 1);
+        assert_eq!(access.status, AccessStatus::Granted);
+        assert_eq!(
+            listing.require_open_dispute().unwrap_err(),
+            IntelMarketError::NoActiveDispute.into()
+        );
+    }
+
+    #[test]
+    fn arbiters_come_from_config_or_arbiter_team() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (listing, _) = purchased(seller, buyer);
+        let (listed, team) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let config = config(vec![listed], Some(team));
+
+        assert!(require_arbiter(&config, &listed, None, &listing).is_ok());
+
+        let analyst = Pubkey::new_unique();
+        let member = membership(team, analyst, TeamRole::Analyst);
+        assert!(require_arbiter(&config, &analyst, Some(&member), &listing).is_ok());
+
+        let observer = Pubkey::new_unique();
+        let member = membership(team, observer, TeamRole::Observer);
+        assert_eq!(
+            require_arbiter(&config, &observer, Some(&member), &listing).unwrap_err(),
+            IntelMarketError::NotArbiter.into()
+        );
+
+        let outsider = Pubkey::new_unique();
+        let member = membership(Pubkey::new_unique(), outsider, TeamRole::Admin);
+        assert_eq!(
+            require_arbiter(&config, &outsider, Some(&member), &listing).unwrap_err(),
+            IntelMarketError::NotArbiter.into()
+        );
+    }
+
+    #[test]
+    fn parties_cannot_arbitrate_their_own_dispute() {
+        let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (listing, _) = purchased(seller, buyer);
+        let config = config(vec![seller, buyer], None);
+        for party in [seller, buyer] {
+            assert_eq!(
+                require_arbiter(&config, &party, None, &listing).unwrap_err(),
+                IntelMarketError::NotArbiter.into()
+            );
+        }
+    }
+
+    #[test]
+    fn payout_takes_fee_then_splits_shares() {
+        let mut listing = listing(Pubkey::new_unique());
+        listing.shares = vec![
+            RevenueShare {
+                recipient: Pubkey::new_unique(),
+                bps: 3_333,
+            },
+            RevenueShare {
+                recipient: Pubkey::new_unique(),
+                bps: 6_667,
+            },
+        ];
+        let payout = listing.payout(1_000_001, 250);
+        assert_eq!(payout.fee, 25_000);
+        assert_eq!(payout.shares, vec![324_967, 650_033]);
+        assert_eq!(payout.seller, 1); // Rounding dust
+        assert_eq!(
+            payout.fee + payout.shares.iter().sum::<u64>() + payout.seller,
+            1_000_001
+        );
+    }
+
+    #[test]
+    fn revenue_shares_must_cover_rent_after_fee() {
+        let mut listing = listing(Pubkey::new_unique());
+        listing.price = 2_000;
+        listing.shares = vec![
+            RevenueShare {
+                recipient: Pubkey::new_unique(),
+                bps: 5_000,
+            },
+            RevenueShare {
+                recipient: Pubkey::new_unique(),
+                bps: 5_000,
+            },
+        ];
+        listing.require_rent_exempt_shares(0, 1_000).unwrap();
+        assert_eq!(
+            listing.require_rent_exempt_shares(250, 1_000).unwrap_err(),
+            IntelMarketError::RevenueShareBelowRent.into()
+        );
+    }
 }
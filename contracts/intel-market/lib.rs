@@ -0,0 +1,331 @@
+// Program entry point for the Intelligence Exchange Market. Accounts, validation and
+// instruction handlers live in intel_report.rs; the program module below only routes
+// each instruction to its handler.
+
+// The cpi wrappers generated for create, update and supersede take every instruction
+// argument.
+#![allow(clippy::too_many_arguments)]
+
+use anchor_lang::prelude::*;
+
+pub mod intel_report;
+
+// Handlers share their names with the instructions below; only the account types, events
+// and generated client modules are reached through this re-export.
+#[allow(ambiguous_glob_reexports)]
+pub use intel_report::*;
+
+declare_id!("DrVa94UitBfpr2JTidWhAHgKZtM3rvDHdYdrua31TnvP");
+
+#[program]
+pub mod intel_market {
+    use super::*;
+
+    pub fn initialize_author_state(ctx: Context<InitializeAuthorState>) -> Result<()> {
+        intel_report::initialize_author_state(ctx)
+    }
+
+    pub fn initialize_geo_cell(ctx: Context<InitializeGeoCell>, cell: [u8; 4]) -> Result<()> {
+        intel_report::initialize_geo_cell(ctx, cell)
+    }
+
+    pub fn initialize_tag_registry(ctx: Context<InitializeTagRegistry>) -> Result<()> {
+        intel_report::initialize_tag_registry(ctx)
+    }
+
+    pub fn register_tag(ctx: Context<RegisterTag>, name: String) -> Result<()> {
+        intel_report::register_tag(ctx, name)
+    }
+
+    pub fn index_report_tag(ctx: Context<IndexReportTag>) -> Result<()> {
+        intel_report::index_report_tag(ctx)
+    }
+
+    pub fn unindex_report_tag(ctx: Context<UnindexReportTag>) -> Result<()> {
+        intel_report::unindex_report_tag(ctx)
+    }
+
+    pub fn create_intel_report(
+        ctx: Context<CreateIntelReport>,
+        sequence: u64,
+        title: String,
+        content: ReportContent,
+        tags: Vec<u16>,
+        latitude: i32,
+        longitude: i32,
+        precision: LocationPrecision,
+        observed_at: i64,
+        classification: Classification,
+        team: Option<Pubkey>,
+    ) -> Result<()> {
+        intel_report::create_intel_report(
+            ctx,
+            sequence,
+            title,
+            content,
+            tags,
+            latitude,
+            longitude,
+            precision,
+            observed_at,
+            classification,
+            team,
+        )
+    }
+
+    pub fn update_intel_report(
+        ctx: Context<UpdateIntelReport>,
+        title: Option<String>,
+        content: Option<ReportContent>,
+        tags: Option<Vec<u16>>,
+        latitude: Option<i32>,
+        longitude: Option<i32>,
+        precision: Option<LocationPrecision>,
+        classification: Option<Classification>,
+    ) -> Result<()> {
+        intel_report::update_intel_report(
+            ctx,
+            title,
+            content,
+            tags,
+            latitude,
+            longitude,
+            precision,
+            classification,
+        )
+    }
+
+    pub fn supersede_intel_report(
+        ctx: Context<SupersedeIntelReport>,
+        sequence: u64,
+        title: String,
+        content: ReportContent,
+        tags: Vec<u16>,
+        latitude: i32,
+        longitude: i32,
+        precision: LocationPrecision,
+        observed_at: i64,
+        classification: Classification,
+    ) -> Result<()> {
+        intel_report::supersede_intel_report(
+            ctx,
+            sequence,
+            title,
+            content,
+            tags,
+            latitude,
+            longitude,
+            precision,
+            observed_at,
+            classification,
+        )
+    }
+
+    pub fn retract_intel_report(ctx: Context<RetractIntelReport>) -> Result<()> {
+        intel_report::retract_intel_report(ctx)
+    }
+
+    pub fn create_cyber_team(
+        ctx: Context<CreateCyberTeam>,
+        name: String,
+        open: bool,
+    ) -> Result<()> {
+        intel_report::create_cyber_team(ctx, name, open)
+    }
+
+    pub fn invite_member(
+        ctx: Context<InviteMember>,
+        invitee: Pubkey,
+        role: TeamRole,
+    ) -> Result<()> {
+        intel_report::invite_member(ctx, invitee, role)
+    }
+
+    pub fn accept_invitation(ctx: Context<AcceptInvitation>) -> Result<()> {
+        intel_report::accept_invitation(ctx)
+    }
+
+    pub fn close_invitation(ctx: Context<CloseInvitation>) -> Result<()> {
+        intel_report::close_invitation(ctx)
+    }
+
+    pub fn set_member_role(ctx: Context<SetMemberRole>, role: TeamRole) -> Result<()> {
+        intel_report::set_member_role(ctx, role)
+    }
+
+    pub fn remove_member(ctx: Context<RemoveMember>) -> Result<()> {
+        intel_report::remove_member(ctx)
+    }
+
+    pub fn join_cyber_team(ctx: Context<JoinCyberTeam>) -> Result<()> {
+        intel_report::join_cyber_team(ctx)
+    }
+
+    pub fn leave_cyber_team(ctx: Context<LeaveCyberTeam>) -> Result<()> {
+        intel_report::leave_cyber_team(ctx)
+    }
+
+    pub fn create_team_proposal(
+        ctx: Context<CreateTeamProposal>,
+        action: ProposalAction,
+    ) -> Result<()> {
+        intel_report::create_team_proposal(ctx, action)
+    }
+
+    pub fn approve_team_proposal(ctx: Context<ApproveTeamProposal>) -> Result<()> {
+        intel_report::approve_team_proposal(ctx)
+    }
+
+    pub fn execute_add_member_proposal(
+        ctx: Context<ExecuteAddMemberProposal>,
+        member: Pubkey,
+    ) -> Result<()> {
+        intel_report::execute_add_member_proposal(ctx, member)
+    }
+
+    pub fn execute_remove_member_proposal(ctx: Context<ExecuteRemoveMemberProposal>) -> Result<()> {
+        intel_report::execute_remove_member_proposal(ctx)
+    }
+
+    pub fn execute_team_proposal(ctx: Context<ExecuteTeamProposal>) -> Result<()> {
+        intel_report::execute_team_proposal(ctx)
+    }
+
+    pub fn close_expired_proposal(ctx: Context<CloseExpiredProposal>) -> Result<()> {
+        intel_report::close_expired_proposal(ctx)
+    }
+
+    pub fn migrate_intel_report(ctx: Context<MigrateAccount>) -> Result<()> {
+        intel_report::migrate_intel_report(ctx)
+    }
+
+    pub fn migrate_cyber_team<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateAccount<'info>>,
+    ) -> Result<()> {
+        intel_report::migrate_cyber_team(ctx)
+    }
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_bps: u16,
+        fee_recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        intel_report::initialize_config(ctx, fee_bps, fee_recipient)
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        new_admin: Pubkey,
+        fee_bps: u16,
+        fee_recipient: Option<Pubkey>,
+        max_content_len: u32,
+        fees: FeeSchedule,
+    ) -> Result<()> {
+        intel_report::update_config(
+            ctx,
+            new_admin,
+            fee_bps,
+            fee_recipient,
+            max_content_len,
+            fees,
+        )
+    }
+
+    pub fn set_rate_limits(
+        ctx: Context<UpdateConfig>,
+        min_report_interval: u32,
+        daily_report_cap: u32,
+    ) -> Result<()> {
+        intel_report::set_rate_limits(ctx, min_report_interval, daily_report_cap)
+    }
+
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        intel_report::set_paused(ctx, paused)
+    }
+
+    pub fn set_arbiters(
+        ctx: Context<UpdateConfig>,
+        arbiters: Vec<Pubkey>,
+        arbiter_team: Option<Pubkey>,
+    ) -> Result<()> {
+        intel_report::set_arbiters(ctx, arbiters, arbiter_team)
+    }
+
+    pub fn list_intel_report(
+        ctx: Context<ListIntelReport>,
+        price: u64,
+        shares: Vec<RevenueShare>,
+    ) -> Result<()> {
+        intel_report::list_intel_report(ctx, price, shares)
+    }
+
+    pub fn list_intel_report_spl(
+        ctx: Context<ListIntelReportSpl>,
+        price: u64,
+        shares: Vec<RevenueShare>,
+    ) -> Result<()> {
+        intel_report::list_intel_report_spl(ctx, price, shares)
+    }
+
+    pub fn purchase_intel_report(ctx: Context<PurchaseIntelReport>) -> Result<()> {
+        intel_report::purchase_intel_report(ctx)
+    }
+
+    pub fn purchase_intel_report_spl(ctx: Context<PurchaseIntelReportSpl>) -> Result<()> {
+        intel_report::purchase_intel_report_spl(ctx)
+    }
+
+    pub fn settle_purchase<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettlePurchase<'info>>,
+    ) -> Result<()> {
+        intel_report::settle_purchase(ctx)
+    }
+
+    pub fn settle_purchase_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettlePurchaseSpl<'info>>,
+    ) -> Result<()> {
+        intel_report::settle_purchase_spl(ctx)
+    }
+
+    pub fn open_dispute(ctx: Context<OpenDispute>) -> Result<()> {
+        intel_report::open_dispute(ctx)
+    }
+
+    pub fn resolve_dispute<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveDispute<'info>>,
+        outcome: DisputeOutcome,
+    ) -> Result<()> {
+        intel_report::resolve_dispute(ctx, outcome)
+    }
+
+    pub fn resolve_dispute_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveDisputeSpl<'info>>,
+        outcome: DisputeOutcome,
+    ) -> Result<()> {
+        intel_report::resolve_dispute_spl(ctx, outcome)
+    }
+
+    pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
+        intel_report::cancel_listing(ctx)
+    }
+
+    pub fn initialize_reputation(ctx: Context<InitializeReputation>, author: Pubkey) -> Result<()> {
+        intel_report::initialize_reputation(ctx, author)
+    }
+
+    pub fn rate_intel_report(ctx: Context<RateIntelReport>, score: u8) -> Result<()> {
+        intel_report::rate_intel_report(ctx, score)
+    }
+
+    pub fn attach_evidence(
+        ctx: Context<AttachEvidence>,
+        content_hash: [u8; 32],
+        media_type: String,
+    ) -> Result<()> {
+        intel_report::attach_evidence(ctx, content_hash, media_type)
+    }
+
+    pub fn transfer_custody(ctx: Context<TransferCustody>) -> Result<()> {
+        intel_report::transfer_custody(ctx)
+    }
+}
@@ -0,0 +1,1179 @@
+// Fixtures shared by the instruction-level tests: PDAs, instruction builders and the
+// program's setup flow (config, tag registry, authors and their reports).
+#![allow(dead_code)] // Each test crate uses a different subset
+
+pub mod runtime;
+
+pub use runtime::*;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::spl_token;
+use intel_market::{
+    accounts, canonical_tag_name, instruction, AuthorReputation, AuthorState, Classification,
+    CyberTeam, DisputeOutcome, Evidence, GeoCell, IntelReport, Listing, LocationPrecision,
+    ProgramConfig, ProposalAction, ReportAccess, ReportContent, ReportRating, RevenueShare, Tag,
+    TagEntry, TagName, TagRegistry, TeamInvitation, TeamMembership, TeamProposal, TeamRole,
+};
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: intel_market::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &intel_market::ID).0
+}
+
+pub fn config_pda() -> Pubkey {
+    pda(&[ProgramConfig::SEED])
+}
+
+pub fn tag_registry_pda() -> Pubkey {
+    pda(&[TagRegistry::SEED])
+}
+
+pub fn author_state_pda(author: &Pubkey) -> Pubkey {
+    pda(&[AuthorState::SEED, author.as_ref()])
+}
+
+pub fn report_pda(author: &Pubkey, sequence: u64) -> Pubkey {
+    pda(&[IntelReport::SEED, author.as_ref(), &sequence.to_le_bytes()])
+}
+
+pub fn listing_pda(report: &Pubkey) -> Pubkey {
+    pda(&[Listing::SEED, report.as_ref()])
+}
+
+pub fn access_pda(report: &Pubkey, buyer: &Pubkey) -> Pubkey {
+    pda(&[ReportAccess::SEED, report.as_ref(), buyer.as_ref()])
+}
+
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[intel_market::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+pub fn reputation_pda(author: &Pubkey) -> Pubkey {
+    pda(&[AuthorReputation::SEED, author.as_ref()])
+}
+
+pub fn tag_entry_pda(id: u16, report: &Pubkey) -> Pubkey {
+    pda(&[TagEntry::SEED, &id.to_le_bytes(), report.as_ref()])
+}
+
+pub fn geo_cell_pda(cell: &[u8; 4]) -> Pubkey {
+    pda(&[GeoCell::SEED, cell])
+}
+
+pub fn rating_pda(report: &Pubkey, rater: &Pubkey) -> Pubkey {
+    pda(&[ReportRating::SEED, report.as_ref(), rater.as_ref()])
+}
+
+pub fn membership_pda(team: &Pubkey, member: &Pubkey) -> Pubkey {
+    pda(&[TeamMembership::SEED, team.as_ref(), member.as_ref()])
+}
+
+pub fn invitation_pda(team: &Pubkey, invitee: &Pubkey) -> Pubkey {
+    pda(&[TeamInvitation::SEED, team.as_ref(), invitee.as_ref()])
+}
+
+pub fn proposal_pda(team: &Pubkey, index: u64) -> Pubkey {
+    pda(&[TeamProposal::SEED, team.as_ref(), &index.to_le_bytes()])
+}
+
+pub fn evidence_pda(report: &Pubkey, content_hash: &[u8; 32]) -> Pubkey {
+    pda(&[Evidence::SEED, report.as_ref(), content_hash])
+}
+
+// Config admin, fee recipient and arbiter of a market set up by `setup`
+pub struct Market {
+    pub admin: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub arbiter: Pubkey,
+}
+
+pub const FEE_BPS: u16 = 250;
+
+pub fn initialize_config(
+    runtime: &mut Runtime,
+    admin: &Pubkey,
+    fee_bps: u16,
+    fee_recipient: Option<Pubkey>,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::InitializeConfig {
+            config: config_pda(),
+            admin: *admin,
+            program: intel_market::ID,
+            program_data: program_data_address(),
+            system_program: System::id(),
+        },
+        instruction::InitializeConfig {
+            fee_bps,
+            fee_recipient,
+        },
+    );
+    runtime.send(&[instruction], &[*admin])
+}
+
+pub fn set_paused(
+    runtime: &mut Runtime,
+    admin: &Pubkey,
+    paused: bool,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::UpdateConfig {
+            config: config_pda(),
+            admin: *admin,
+        },
+        instruction::SetPaused { paused },
+    );
+    runtime.send(&[instruction], &[*admin])
+}
+
+// Config with a protocol fee and one arbiter, plus the tag registry
+pub fn setup(runtime: &mut Runtime) -> Market {
+    let admin = runtime.upgrade_authority;
+    let market = Market {
+        admin,
+        fee_recipient: runtime.wallet(LAMPORTS_PER_SOL),
+        arbiter: runtime.wallet(LAMPORTS_PER_SOL),
+    };
+    initialize_config(runtime, &admin, FEE_BPS, Some(market.fee_recipient)).unwrap();
+    let set_arbiters = ix(
+        accounts::UpdateConfig {
+            config: config_pda(),
+            admin,
+        },
+        instruction::SetArbiters {
+            arbiters: vec![market.arbiter],
+            arbiter_team: None,
+        },
+    );
+    let registry = ix(
+        accounts::InitializeTagRegistry {
+            config: config_pda(),
+            tag_registry: tag_registry_pda(),
+            payer: admin,
+            system_program: System::id(),
+        },
+        instruction::InitializeTagRegistry {},
+    );
+    runtime.send(&[set_arbiters, registry], &[admin]).unwrap();
+    market
+}
+
+pub fn tag_pda(id: u16) -> Pubkey {
+    pda(&[Tag::SEED, &id.to_le_bytes()])
+}
+
+// Registers `name` as the registry's next tag and returns its ID
+pub fn register_tag(
+    runtime: &mut Runtime,
+    admin: &Pubkey,
+    name: &str,
+) -> std::result::Result<u16, TxError> {
+    let id = runtime.get::<TagRegistry>(&tag_registry_pda()).tag_count;
+    let instruction = ix(
+        accounts::RegisterTag {
+            config: config_pda(),
+            tag_registry: tag_registry_pda(),
+            tag: tag_pda(id),
+            tag_name: pda(&[TagName::SEED, canonical_tag_name(name).as_bytes()]),
+            admin: *admin,
+            system_program: System::id(),
+        },
+        instruction::RegisterTag {
+            name: name.to_string(),
+        },
+    );
+    runtime.send(&[instruction], &[*admin])?;
+    Ok(id)
+}
+
+// Creates an inline, untagged report as `author`'s next sequence number
+pub fn create_report(runtime: &mut Runtime, author: &Pubkey) -> Pubkey {
+    create_report_at(
+        runtime,
+        author,
+        48_850_000,
+        2_350_000,
+        LocationPrecision::City,
+    )
+    .unwrap()
+}
+
+// Like create_report, at the given location
+pub fn create_report_at(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    latitude: i32,
+    longitude: i32,
+    precision: LocationPrecision,
+) -> std::result::Result<Pubkey, TxError> {
+    let report = NewReport {
+        latitude,
+        longitude,
+        precision,
+        ..NewReport::default()
+    };
+    create_report_with(runtime, author, report)
+}
+
+// The fields of a report to create; observed_at defaults to the current time
+pub struct NewReport {
+    pub latitude: i32,
+    pub longitude: i32,
+    pub precision: LocationPrecision,
+    pub content: ReportContent,
+    pub tags: Vec<u16>,
+    pub observed_at: Option<i64>,
+    pub team: Option<Pubkey>,
+    pub geo_cell: Option<Pubkey>,
+}
+
+impl Default for NewReport {
+    fn default() -> Self {
+        NewReport {
+            latitude: 48_850_000,
+            longitude: 2_350_000,
+            precision: LocationPrecision::City,
+            content: ReportContent::Inline("C2 traffic every 60s".to_string()),
+            tags: vec![],
+            observed_at: None,
+            team: None,
+            geo_cell: None,
+        }
+    }
+}
+
+// Creates `report` as `author`'s next sequence number, initializing their AuthorState
+// first if needed. A team report passes the author's membership in that team.
+pub fn create_report_with(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    report: NewReport,
+) -> std::result::Result<Pubkey, TxError> {
+    let author_state = author_state_pda(author);
+    if runtime.account(&author_state).is_none() {
+        let initialize = ix(
+            accounts::InitializeAuthorState {
+                config: config_pda(),
+                author_state,
+                author: *author,
+                system_program: System::id(),
+            },
+            instruction::InitializeAuthorState {},
+        );
+        runtime.send(&[initialize], &[*author]).unwrap();
+    }
+    let sequence = runtime.get::<AuthorState>(&author_state).report_count;
+    let key = report_pda(author, sequence);
+    let create = ix(
+        accounts::CreateIntelReport {
+            config: config_pda(),
+            intel_report: key,
+            author_state,
+            tag_registry: tag_registry_pda(),
+            team_membership: report.team.map(|team| membership_pda(&team, author)),
+            geo_cell: report.geo_cell,
+            fee_recipient: None,
+            author: *author,
+            system_program: System::id(),
+        },
+        instruction::CreateIntelReport {
+            sequence,
+            title: "Beaconing host".to_string(),
+            content: report.content,
+            tags: report.tags,
+            latitude: report.latitude,
+            longitude: report.longitude,
+            precision: report.precision,
+            observed_at: report.observed_at.unwrap_or(runtime.clock.unix_timestamp),
+            classification: Classification::Unclassified,
+            team: report.team,
+        },
+    );
+    runtime.send(&[create], &[*author])?;
+    Ok(key)
+}
+
+pub fn index_tag(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    report: &Pubkey,
+    id: u16,
+) -> std::result::Result<Pubkey, TxError> {
+    let tag_entry = tag_entry_pda(id, report);
+    let instruction = ix(
+        accounts::IndexReportTag {
+            config: config_pda(),
+            tag: tag_pda(id),
+            intel_report: *report,
+            tag_entry,
+            author: *author,
+            system_program: System::id(),
+        },
+        instruction::IndexReportTag {},
+    );
+    runtime.send(&[instruction], &[*author])?;
+    Ok(tag_entry)
+}
+
+// Removes the tag's entry for `report`, with `caller` paying the fee
+pub fn unindex_tag(
+    runtime: &mut Runtime,
+    caller: &Pubkey,
+    report: &Pubkey,
+    id: u16,
+) -> std::result::Result<(), TxError> {
+    let tag_entry = tag_entry_pda(id, report);
+    let instruction = ix(
+        accounts::UnindexReportTag {
+            config: config_pda(),
+            tag: tag_pda(id),
+            intel_report: *report,
+            tag_entry,
+            payer: runtime.get::<TagEntry>(&tag_entry).payer,
+        },
+        instruction::UnindexReportTag {},
+    );
+    runtime.send(&[instruction], &[*caller])
+}
+
+// Replaces the report's title, leaving every other field unchanged
+pub fn update_title(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    report: &Pubkey,
+    title: &str,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::UpdateIntelReport {
+            config: config_pda(),
+            intel_report: *report,
+            listing: listing_pda(report),
+            tag_registry: None,
+            previous_geo_cell: None,
+            geo_cell: None,
+            author: *author,
+            system_program: System::id(),
+        },
+        instruction::UpdateIntelReport {
+            title: Some(title.to_string()),
+            content: None,
+            tags: None,
+            latitude: None,
+            longitude: None,
+            precision: None,
+            classification: None,
+        },
+    );
+    runtime.send(&[instruction], &[*author])
+}
+
+// Moves the report, leaving every other field unchanged
+pub fn update_location(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    report: &Pubkey,
+    coordinates: Option<(i32, i32)>,
+    precision: LocationPrecision,
+) -> std::result::Result<(), TxError> {
+    move_report(runtime, author, report, coordinates, precision, None, None)
+}
+
+// Like update_location, passing the GeoCells the report moves between
+pub fn move_report(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    report: &Pubkey,
+    coordinates: Option<(i32, i32)>,
+    precision: LocationPrecision,
+    previous_geo_cell: Option<Pubkey>,
+    geo_cell: Option<Pubkey>,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::UpdateIntelReport {
+            config: config_pda(),
+            intel_report: *report,
+            listing: listing_pda(report),
+            tag_registry: None,
+            previous_geo_cell,
+            geo_cell,
+            author: *author,
+            system_program: System::id(),
+        },
+        instruction::UpdateIntelReport {
+            title: None,
+            content: None,
+            tags: None,
+            latitude: coordinates.map(|(latitude, _)| latitude),
+            longitude: coordinates.map(|(_, longitude)| longitude),
+            precision: Some(precision),
+            classification: None,
+        },
+    );
+    runtime.send(&[instruction], &[*author])
+}
+
+pub fn retract(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    report: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    retract_from(runtime, author, report, None)
+}
+
+// Like retract, passing the GeoCell the report is counted in
+pub fn retract_from(
+    runtime: &mut Runtime,
+    author: &Pubkey,
+    report: &Pubkey,
+    geo_cell: Option<Pubkey>,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::RetractIntelReport {
+            config: config_pda(),
+            intel_report: *report,
+            listing: listing_pda(report),
+            geo_cell,
+            author: *author,
+        },
+        instruction::RetractIntelReport {},
+    );
+    runtime.send(&[instruction], &[*author])
+}
+
+// Opens the GeoCell index for `cell`
+pub fn initialize_geo_cell(
+    runtime: &mut Runtime,
+    payer: &Pubkey,
+    cell: [u8; 4],
+) -> std::result::Result<Pubkey, TxError> {
+    let geo_cell = geo_cell_pda(&cell);
+    let instruction = ix(
+        accounts::InitializeGeoCell {
+            config: config_pda(),
+            geo_cell,
+            payer: *payer,
+            system_program: System::id(),
+        },
+        instruction::InitializeGeoCell { cell },
+    );
+    runtime.send(&[instruction], &[*payer])?;
+    Ok(geo_cell)
+}
+
+// Publishes a new revision of `previous` and returns its address
+pub fn supersede(runtime: &mut Runtime, author: &Pubkey, previous: &Pubkey) -> Pubkey {
+    let author_state = author_state_pda(author);
+    let sequence = runtime.get::<AuthorState>(&author_state).report_count;
+    let report = report_pda(author, sequence);
+    let instruction = ix(
+        accounts::SupersedeIntelReport {
+            config: config_pda(),
+            previous_report: *previous,
+            intel_report: report,
+            author_state,
+            tag_registry: tag_registry_pda(),
+            team_membership: None,
+            geo_cell: None,
+            fee_recipient: None,
+            author: *author,
+            system_program: System::id(),
+        },
+        instruction::SupersedeIntelReport {
+            sequence,
+            title: "Beaconing host, revised".to_string(),
+            content: ReportContent::Inline("C2 traffic every 30s".to_string()),
+            tags: vec![],
+            latitude: 48_850_000,
+            longitude: 2_350_000,
+            precision: LocationPrecision::City,
+            observed_at: runtime.clock.unix_timestamp,
+            classification: Classification::Unclassified,
+        },
+    );
+    runtime.send(&[instruction], &[*author]).unwrap();
+    report
+}
+
+pub fn list(
+    runtime: &mut Runtime,
+    seller: &Pubkey,
+    report: &Pubkey,
+    price: u64,
+    shares: Vec<RevenueShare>,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::ListIntelReport {
+            config: config_pda(),
+            intel_report: *report,
+            listing: listing_pda(report),
+            fee_recipient: None,
+            seller: *seller,
+            system_program: System::id(),
+        },
+        instruction::ListIntelReport { price, shares },
+    );
+    runtime.send(&[instruction], &[*seller])
+}
+
+pub fn list_spl(
+    runtime: &mut Runtime,
+    seller: &Pubkey,
+    report: &Pubkey,
+    mint: &Pubkey,
+    price: u64,
+) -> std::result::Result<(), TxError> {
+    let listing = listing_pda(report);
+    let instruction = ix(
+        accounts::ListIntelReportSpl {
+            config: config_pda(),
+            intel_report: *report,
+            listing,
+            payment_mint: *mint,
+            escrow: get_associated_token_address(&listing, mint),
+            fee_recipient: None,
+            seller: *seller,
+            token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: System::id(),
+        },
+        instruction::ListIntelReportSpl {
+            price,
+            shares: vec![],
+        },
+    );
+    runtime.send(&[instruction], &[*seller])
+}
+
+pub fn purchase(
+    runtime: &mut Runtime,
+    buyer: &Pubkey,
+    report: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::PurchaseIntelReport {
+            config: config_pda(),
+            listing: listing_pda(report),
+            intel_report: *report,
+            access: access_pda(report, buyer),
+            buyer: *buyer,
+            system_program: System::id(),
+        },
+        instruction::PurchaseIntelReport {},
+    );
+    runtime.send(&[instruction], &[*buyer])
+}
+
+pub fn purchase_spl(
+    runtime: &mut Runtime,
+    buyer: &Pubkey,
+    report: &Pubkey,
+    mint: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let listing = listing_pda(report);
+    let instruction = ix(
+        accounts::PurchaseIntelReportSpl {
+            config: config_pda(),
+            listing,
+            intel_report: *report,
+            access: access_pda(report, buyer),
+            payment_mint: *mint,
+            buyer_token: get_associated_token_address(buyer, mint),
+            escrow: get_associated_token_address(&listing, mint),
+            buyer: *buyer,
+            token_program: spl_token::ID,
+            system_program: System::id(),
+        },
+        instruction::PurchaseIntelReportSpl {},
+    );
+    runtime.send(&[instruction], &[*buyer])
+}
+
+// `share_accounts` are appended as the revenue share remaining accounts
+pub fn settle(
+    runtime: &mut Runtime,
+    market: &Market,
+    authority: &Pubkey,
+    report: &Pubkey,
+    buyer: &Pubkey,
+    share_accounts: &[Pubkey],
+) -> std::result::Result<(), TxError> {
+    let listing = listing_pda(report);
+    let seller = runtime.get::<Listing>(&listing).seller;
+    let mut instruction = ix(
+        accounts::SettlePurchase {
+            listing,
+            access: access_pda(report, buyer),
+            intel_report: *report,
+            seller,
+            config: config_pda(),
+            fee_recipient: Some(market.fee_recipient),
+            authority: *authority,
+        },
+        instruction::SettlePurchase {},
+    );
+    instruction.accounts.extend(
+        share_accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false)),
+    );
+    runtime.send(&[instruction], &[*authority])
+}
+
+pub fn settle_spl(
+    runtime: &mut Runtime,
+    market: &Market,
+    authority: &Pubkey,
+    report: &Pubkey,
+    buyer: &Pubkey,
+    mint: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let listing = listing_pda(report);
+    let seller = runtime.get::<Listing>(&listing).seller;
+    let instruction = ix(
+        accounts::SettlePurchaseSpl {
+            listing,
+            access: access_pda(report, buyer),
+            intel_report: *report,
+            payment_mint: *mint,
+            escrow: get_associated_token_address(&listing, mint),
+            seller_token: get_associated_token_address(&seller, mint),
+            config: config_pda(),
+            fee_token: Some(get_associated_token_address(&market.fee_recipient, mint)),
+            authority: *authority,
+            token_program: spl_token::ID,
+        },
+        instruction::SettlePurchaseSpl {},
+    );
+    runtime.send(&[instruction], &[*authority])
+}
+
+pub fn open_dispute(
+    runtime: &mut Runtime,
+    buyer: &Pubkey,
+    report: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::OpenDispute {
+            config: config_pda(),
+            listing: listing_pda(report),
+            buyer: *buyer,
+        },
+        instruction::OpenDispute {},
+    );
+    runtime.send(&[instruction], &[*buyer])
+}
+
+pub fn initialize_reputation(runtime: &mut Runtime, payer: &Pubkey, author: &Pubkey) {
+    let instruction = ix(
+        accounts::InitializeReputation {
+            config: config_pda(),
+            reputation: reputation_pda(author),
+            payer: *payer,
+            system_program: System::id(),
+        },
+        instruction::InitializeReputation { author: *author },
+    );
+    runtime.send(&[instruction], &[*payer]).unwrap();
+}
+
+pub fn rate(
+    runtime: &mut Runtime,
+    rater: &Pubkey,
+    report: &Pubkey,
+    score: u8,
+) -> std::result::Result<(), TxError> {
+    let author = runtime.get::<IntelReport>(report).author;
+    let instruction = ix(
+        accounts::RateIntelReport {
+            config: config_pda(),
+            intel_report: *report,
+            access: access_pda(report, rater),
+            rating: rating_pda(report, rater),
+            reputation: reputation_pda(&author),
+            rater: *rater,
+            system_program: System::id(),
+        },
+        instruction::RateIntelReport { score },
+    );
+    runtime.send(&[instruction], &[*rater])
+}
+
+pub fn resolve_dispute(
+    runtime: &mut Runtime,
+    market: &Market,
+    report: &Pubkey,
+    buyer: &Pubkey,
+    outcome: DisputeOutcome,
+) -> std::result::Result<(), TxError> {
+    let listing = listing_pda(report);
+    let seller = runtime.get::<Listing>(&listing).seller;
+    let instruction = ix(
+        accounts::ResolveDispute {
+            listing,
+            access: access_pda(report, buyer),
+            buyer: *buyer,
+            seller,
+            reputation: existing(runtime, reputation_pda(&seller)),
+            config: config_pda(),
+            fee_recipient: Some(market.fee_recipient),
+            arbiter_membership: None,
+            arbiter: market.arbiter,
+        },
+        instruction::ResolveDispute { outcome },
+    );
+    runtime.send(&[instruction], &[market.arbiter])
+}
+
+pub fn resolve_dispute_spl(
+    runtime: &mut Runtime,
+    market: &Market,
+    report: &Pubkey,
+    buyer: &Pubkey,
+    mint: &Pubkey,
+    outcome: DisputeOutcome,
+) -> std::result::Result<(), TxError> {
+    let listing = listing_pda(report);
+    let seller = runtime.get::<Listing>(&listing).seller;
+    let instruction = ix(
+        accounts::ResolveDisputeSpl {
+            listing,
+            access: access_pda(report, buyer),
+            buyer: *buyer,
+            payment_mint: *mint,
+            escrow: get_associated_token_address(&listing, mint),
+            buyer_token: get_associated_token_address(buyer, mint),
+            seller_token: get_associated_token_address(&seller, mint),
+            reputation: existing(runtime, reputation_pda(&seller)),
+            config: config_pda(),
+            fee_token: Some(get_associated_token_address(&market.fee_recipient, mint)),
+            arbiter_membership: None,
+            arbiter: market.arbiter,
+            token_program: spl_token::ID,
+        },
+        instruction::ResolveDisputeSpl { outcome },
+    );
+    runtime.send(&[instruction], &[market.arbiter])
+}
+
+// `buyer` is the pending buyer to refund, if any
+pub fn cancel(
+    runtime: &mut Runtime,
+    seller: &Pubkey,
+    report: &Pubkey,
+    buyer: Option<&Pubkey>,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::CancelListing {
+            config: config_pda(),
+            listing: listing_pda(report),
+            seller: *seller,
+            buyer: buyer.copied(),
+            access: buyer.map(|buyer| access_pda(report, buyer)),
+            payment_mint: None,
+            escrow: None,
+            buyer_token: None,
+            seller_token: None,
+            token_program: None,
+        },
+        instruction::CancelListing {},
+    );
+    runtime.send(&[instruction], &[*seller])
+}
+
+pub fn cancel_spl(
+    runtime: &mut Runtime,
+    seller: &Pubkey,
+    report: &Pubkey,
+    mint: &Pubkey,
+    buyer: Option<&Pubkey>,
+) -> std::result::Result<(), TxError> {
+    let listing = listing_pda(report);
+    let instruction = ix(
+        accounts::CancelListing {
+            config: config_pda(),
+            listing,
+            seller: *seller,
+            buyer: buyer.copied(),
+            access: buyer.map(|buyer| access_pda(report, buyer)),
+            payment_mint: Some(*mint),
+            escrow: Some(get_associated_token_address(&listing, mint)),
+            buyer_token: buyer.map(|buyer| get_associated_token_address(buyer, mint)),
+            seller_token: Some(get_associated_token_address(seller, mint)),
+            token_program: Some(spl_token::ID),
+        },
+        instruction::CancelListing {},
+    );
+    runtime.send(&[instruction], &[*seller])
+}
+
+// A closed team owned by `owner`, at threshold 1
+pub fn create_team(runtime: &mut Runtime, owner: &Pubkey) -> Pubkey {
+    create_named_team(runtime, owner, "Case room").unwrap()
+}
+
+pub fn create_named_team(
+    runtime: &mut Runtime,
+    owner: &Pubkey,
+    name: &str,
+) -> std::result::Result<Pubkey, TxError> {
+    let team = Pubkey::new_unique();
+    let instruction = ix(
+        accounts::CreateCyberTeam {
+            config: config_pda(),
+            cyber_team: team,
+            owner_membership: membership_pda(&team, owner),
+            authority: *owner,
+            system_program: System::id(),
+        },
+        instruction::CreateCyberTeam {
+            name: name.to_string(),
+            open: false,
+        },
+    );
+    runtime.send(&[instruction], &[*owner, team])?;
+    Ok(team)
+}
+
+pub fn join(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    member: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::JoinCyberTeam {
+            config: config_pda(),
+            cyber_team: *team,
+            membership: membership_pda(team, member),
+            member: *member,
+            system_program: System::id(),
+        },
+        instruction::JoinCyberTeam {},
+    );
+    runtime.send(&[instruction], &[*member])
+}
+
+pub fn invite(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    admin: &Pubkey,
+    invitee: &Pubkey,
+    role: TeamRole,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::InviteMember {
+            config: config_pda(),
+            cyber_team: *team,
+            admin_membership: membership_pda(team, admin),
+            invitation: invitation_pda(team, invitee),
+            admin: *admin,
+            system_program: System::id(),
+        },
+        instruction::InviteMember {
+            invitee: *invitee,
+            role,
+        },
+    );
+    runtime.send(&[instruction], &[*admin])
+}
+
+// Invites a new funded wallet with `role` as `admin` and accepts the invitation
+pub fn add_member(runtime: &mut Runtime, team: &Pubkey, admin: &Pubkey, role: TeamRole) -> Pubkey {
+    let member = runtime.wallet(LAMPORTS_PER_SOL);
+    invite(runtime, team, admin, &member, role).unwrap();
+    accept(runtime, team, &member).unwrap();
+    member
+}
+
+pub fn accept(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    invitee: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let invitation = invitation_pda(team, invitee);
+    let instruction = ix(
+        accounts::AcceptInvitation {
+            config: config_pda(),
+            cyber_team: *team,
+            invitation,
+            membership: membership_pda(team, invitee),
+            payer: runtime.get::<TeamInvitation>(&invitation).payer,
+            invitee: *invitee,
+            system_program: System::id(),
+        },
+        instruction::AcceptInvitation {},
+    );
+    runtime.send(&[instruction], &[*invitee])
+}
+
+// Declines the invitation when `signer` is the invitee, otherwise closes it once expired
+pub fn close_invitation(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    invitee: &Pubkey,
+    signer: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let invitation = invitation_pda(team, invitee);
+    let instruction = ix(
+        accounts::CloseInvitation {
+            config: config_pda(),
+            invitation,
+            payer: runtime.get::<TeamInvitation>(&invitation).payer,
+            signer: *signer,
+        },
+        instruction::CloseInvitation {},
+    );
+    runtime.send(&[instruction], &[*signer])
+}
+
+pub fn leave(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    member: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let membership = membership_pda(team, member);
+    let instruction = ix(
+        accounts::LeaveCyberTeam {
+            config: config_pda(),
+            cyber_team: *team,
+            membership,
+            payer: runtime.get::<TeamMembership>(&membership).payer,
+            member: *member,
+        },
+        instruction::LeaveCyberTeam {},
+    );
+    runtime.send(&[instruction], &[*member])
+}
+
+// Opens the team's next proposal; `target` is the member a removal or role change targets
+pub fn propose(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    proposer: &Pubkey,
+    action: ProposalAction,
+    target: Option<Pubkey>,
+) -> std::result::Result<Pubkey, TxError> {
+    let proposal = proposal_pda(team, runtime.get::<CyberTeam>(team).proposal_count);
+    let instruction = ix(
+        accounts::CreateTeamProposal {
+            config: config_pda(),
+            cyber_team: *team,
+            proposer_membership: membership_pda(team, proposer),
+            target_membership: target.map(|member| membership_pda(team, &member)),
+            proposal,
+            proposer: *proposer,
+            system_program: System::id(),
+        },
+        instruction::CreateTeamProposal { action },
+    );
+    runtime.send(&[instruction], &[*proposer])?;
+    Ok(proposal)
+}
+
+pub fn approve(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    approver: &Pubkey,
+    proposal: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::ApproveTeamProposal {
+            config: config_pda(),
+            cyber_team: *team,
+            approver_membership: membership_pda(team, approver),
+            proposal: *proposal,
+            approver: *approver,
+        },
+        instruction::ApproveTeamProposal {},
+    );
+    runtime.send(&[instruction], &[*approver])
+}
+
+// Executes any proposal with the right instruction, passing the memberships of `approvers`
+pub fn execute(
+    runtime: &mut Runtime,
+    team: &Pubkey,
+    proposal: &Pubkey,
+    approvers: &[Pubkey],
+) -> std::result::Result<(), TxError> {
+    let executor = runtime.wallet(LAMPORTS_PER_SOL);
+    let state = runtime.get::<TeamProposal>(proposal);
+    let mut instruction = match state.action {
+        ProposalAction::AddMember { member, .. } => ix(
+            accounts::ExecuteAddMemberProposal {
+                config: config_pda(),
+                cyber_team: *team,
+                proposal: *proposal,
+                invitation: invitation_pda(team, &member),
+                proposer: state.proposer,
+                executor,
+                system_program: System::id(),
+            },
+            instruction::ExecuteAddMemberProposal { member },
+        ),
+        ProposalAction::RemoveMember { member } => {
+            let membership = membership_pda(team, &member);
+            ix(
+                accounts::ExecuteRemoveMemberProposal {
+                    config: config_pda(),
+                    cyber_team: *team,
+                    proposal: *proposal,
+                    membership,
+                    proposer: state.proposer,
+                    payer: runtime.get::<TeamMembership>(&membership).payer,
+                    executor,
+                },
+                instruction::ExecuteRemoveMemberProposal {},
+            )
+        }
+        ref action => {
+            let member = match action {
+                ProposalAction::ChangeRole { member, .. } => Some(membership_pda(team, member)),
+                _ => None,
+            };
+            ix(
+                accounts::ExecuteTeamProposal {
+                    config: config_pda(),
+                    cyber_team: *team,
+                    proposal: *proposal,
+                    membership: member,
+                    proposer: state.proposer,
+                    executor,
+                },
+                instruction::ExecuteTeamProposal {},
+            )
+        }
+    };
+    instruction.accounts.extend(
+        approvers
+            .iter()
+            .map(|approver| AccountMeta::new_readonly(membership_pda(team, approver), false)),
+    );
+    runtime.send(&[instruction], &[executor])
+}
+
+fn migrate_accounts(account: &Pubkey, payer: &Pubkey) -> accounts::MigrateAccount {
+    accounts::MigrateAccount {
+        config: config_pda(),
+        account: *account,
+        payer: *payer,
+        system_program: System::id(),
+    }
+}
+
+// Passes the Tag accounts of `tags`, onto which the report's string tags are mapped
+pub fn migrate_report(
+    runtime: &mut Runtime,
+    payer: &Pubkey,
+    report: &Pubkey,
+    tags: &[u16],
+) -> std::result::Result<(), TxError> {
+    let mut instruction = ix(
+        migrate_accounts(report, payer),
+        instruction::MigrateIntelReport {},
+    );
+    instruction.accounts.extend(
+        tags.iter()
+            .map(|id| AccountMeta::new_readonly(tag_pda(*id), false)),
+    );
+    runtime.send(&[instruction], &[*payer])
+}
+
+// Passes the membership PDAs of `members`, which the migration creates
+pub fn migrate_team(
+    runtime: &mut Runtime,
+    payer: &Pubkey,
+    team: &Pubkey,
+    members: &[Pubkey],
+) -> std::result::Result<(), TxError> {
+    let mut instruction = ix(
+        migrate_accounts(team, payer),
+        instruction::MigrateCyberTeam {},
+    );
+    instruction.accounts.extend(
+        members
+            .iter()
+            .map(|member| AccountMeta::new(membership_pda(team, member), false)),
+    );
+    runtime.send(&[instruction], &[*payer])
+}
+
+// Attaches a pcap with the given digest, passing the collector's membership in the
+// report's team when they have one
+pub fn attach_evidence(
+    runtime: &mut Runtime,
+    collector: &Pubkey,
+    report: &Pubkey,
+    content_hash: [u8; 32],
+) -> std::result::Result<Pubkey, TxError> {
+    let evidence = evidence_pda(report, &content_hash);
+    let team = runtime.get::<IntelReport>(report).team;
+    let instruction = ix(
+        accounts::AttachEvidence {
+            config: config_pda(),
+            intel_report: *report,
+            team_membership: team
+                .and_then(|team| existing(runtime, membership_pda(&team, collector))),
+            evidence,
+            collector: *collector,
+            system_program: System::id(),
+        },
+        instruction::AttachEvidence {
+            content_hash,
+            media_type: "application/vnd.tcpdump.pcap".to_string(),
+        },
+    );
+    runtime.send(&[instruction], &[*collector])?;
+    Ok(evidence)
+}
+
+pub fn transfer_custody(
+    runtime: &mut Runtime,
+    evidence: &Pubkey,
+    custodian: &Pubkey,
+    new_custodian: &Pubkey,
+) -> std::result::Result<(), TxError> {
+    let instruction = ix(
+        accounts::TransferCustody {
+            config: config_pda(),
+            evidence: *evidence,
+            custodian: *custodian,
+            new_custodian: *new_custodian,
+        },
+        instruction::TransferCustody {},
+    );
+    runtime.send(&[instruction], &[*custodian, *new_custodian])
+}
+
+// Mint with `authority`, plus funded token accounts for each of `holders`
+pub fn token_setup(runtime: &mut Runtime, holders: &[(Pubkey, u64)]) -> Pubkey {
+    let authority = runtime.wallet(LAMPORTS_PER_SOL);
+    let mint = runtime.create_mint(&authority, 6);
+    for (holder, amount) in holders {
+        let account = runtime.create_associated_token_account(&authority, holder, &mint);
+        if *amount > 0 {
+            runtime.mint_to(&mint, &authority, &account, *amount);
+        }
+    }
+    mint
+}
+
+// Passes an optional account only once it has been created
+pub fn existing(runtime: &Runtime, key: Pubkey) -> Option<Pubkey> {
+    runtime.account(&key).map(|_| key)
+}
+
+// The transaction error a program error code surfaces as
+pub fn program_error(error: intel_market::IntelMarketError) -> TxError {
+    anchor_lang::error::Error::from(error).into()
+}
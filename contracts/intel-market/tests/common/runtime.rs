@@ -0,0 +1,751 @@
+// In-process runtime for instruction-level tests, modelled on solana-program-test's
+// processor mode: accounts are serialized in the BPF loader's input layout, programs run
+// natively against the deserialized AccountInfos, and CPIs go through the syscall stubs.
+// It covers what this program touches: the system program (create, transfer, assign,
+// allocate), SPL Token and the associated token account program, plus the runtime's
+// ownership, lamport conservation and rent-state checks. A failed transaction leaves
+// every account unchanged.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use anchor_lang::solana_program::entrypoint::{
+    deserialize, ProgramResult, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER,
+};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::system_instruction::SystemInstruction;
+use anchor_lang::solana_program::{bpf_loader, system_program};
+use anchor_lang::Event;
+use anchor_spl::associated_token::spl_associated_token_account;
+use anchor_spl::token::spl_token;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+// Arbitrary start time, well past zero so hold periods can be measured back from it
+pub const GENESIS_TIMESTAMP: i64 = 1_700_000_000;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxError {
+    Program(ProgramError), // Returned by a program, including Anchor and custom errors
+    Runtime(String),       // Rejected by the runtime checks around a program
+}
+
+impl From<anchor_lang::error::Error> for TxError {
+    fn from(error: anchor_lang::error::Error) -> Self {
+        TxError::Program(error.into())
+    }
+}
+
+// One active program invocation. `baseline` holds the account states the program's own
+// changes are checked against; a CPI moves it forward past the callee's changes.
+struct Frame {
+    program_id: Pubkey,
+    metas: Vec<(Pubkey, bool, bool)>,
+    baseline: HashMap<Pubkey, AccountState>,
+}
+
+// Per-thread execution state reached from the syscall stubs; cargo runs tests in parallel
+#[derive(Default)]
+struct Invocation {
+    clock: Clock,
+    stack: Vec<Frame>, // Active invocations, innermost last
+    return_data: Option<(Pubkey, Vec<u8>)>,
+    logs: Vec<String>,
+    events: Vec<Vec<u8>>,
+    violation: Option<String>, // Set by a failed runtime check inside a CPI
+}
+
+thread_local! {
+    static INVOCATION: RefCell<Invocation> = RefCell::new(Invocation::default());
+}
+
+fn with_invocation<R>(f: impl FnOnce(&mut Invocation) -> R) -> R {
+    INVOCATION.with(|invocation| f(&mut invocation.borrow_mut()))
+}
+
+// Unwinds a CPI that broke a runtime rule; the transaction reports the recorded violation
+fn violation(message: String) -> ProgramError {
+    with_invocation(|invocation| {
+        invocation.violation.get_or_insert(message);
+    });
+    ProgramError::Custom(u32::MAX)
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        with_invocation(|invocation| invocation.logs.push(message.to_string()));
+    }
+
+    fn sol_log_data(&self, data: &[&[u8]]) {
+        with_invocation(|invocation| invocation.events.push(data.concat()));
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = with_invocation(|invocation| invocation.clock.clone());
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write(var_addr as *mut Rent, Rent::default()) };
+        0
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        with_invocation(|invocation| invocation.stack.len() as u64)
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        with_invocation(|invocation| {
+            let program_id = invocation.stack.last().unwrap().program_id;
+            invocation.return_data = (!data.is_empty()).then(|| (program_id, data.to_vec()));
+        });
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_invocation(|invocation| invocation.return_data.clone())
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let (caller, caller_metas, baseline) = with_invocation(|invocation| {
+            let frame = invocation.stack.last().unwrap();
+            (
+                frame.program_id,
+                frame.metas.clone(),
+                frame.baseline.clone(),
+            )
+        });
+        let signers: Vec<Pubkey> = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| violation(format!("{caller}: invalid signer seeds")))?;
+
+        let find = |key: &Pubkey| {
+            account_infos
+                .iter()
+                .find(|info| info.key == key)
+                .ok_or_else(|| violation(format!("{caller}: CPI account {key} not passed")))
+        };
+        let mut metas = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let info = find(&meta.pubkey)?;
+            if meta.is_signer && !info.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(violation(format!(
+                    "{}: signer privilege escalated",
+                    meta.pubkey
+                )));
+            }
+            if meta.is_writable && !info.is_writable {
+                return Err(violation(format!(
+                    "{}: writable privilege escalated",
+                    meta.pubkey
+                )));
+            }
+            metas.push((meta.pubkey, meta.is_signer, meta.is_writable));
+        }
+        let keys = unique_keys(&metas);
+        let pre = keys
+            .iter()
+            .map(|key| {
+                let info = find(key)?;
+                let data = info.try_borrow_data()?.to_vec();
+                Ok(AccountState {
+                    lamports: info.lamports(),
+                    data,
+                    owner: *info.owner,
+                    executable: info.executable,
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, ProgramError>>()?;
+        // The caller's own changes so far must be legal before the callee sees them
+        let before: Vec<AccountState> = keys.iter().map(|key| baseline[key].clone()).collect();
+        // Only part of the caller's accounts are seen here, so lamports are summed on return
+        verify(&caller, &caller_metas, &keys, &before, &pre, false).map_err(violation)?;
+
+        let post = execute(
+            &instruction.program_id,
+            &metas,
+            &keys,
+            &pre,
+            &instruction.data,
+        )?;
+
+        with_invocation(|invocation| {
+            let frame = invocation.stack.last_mut().unwrap();
+            for (key, state) in keys.iter().zip(&post) {
+                frame.baseline.insert(*key, state.clone());
+            }
+        });
+        for (key, state) in keys.iter().zip(post) {
+            let info = find(key)?;
+            **info.try_borrow_mut_lamports()? = state.lamports;
+            if info.data_len() != state.data.len() {
+                info.realloc(state.data.len(), false)?;
+            }
+            info.try_borrow_mut_data()?.copy_from_slice(&state.data);
+            if *info.owner != state.owner {
+                info.assign(&state.owner);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Unique account keys in first-seen order
+fn unique_keys(metas: &[(Pubkey, bool, bool)]) -> Vec<Pubkey> {
+    let mut keys: Vec<Pubkey> = Vec::new();
+    for (key, _, _) in metas {
+        if !keys.contains(key) {
+            keys.push(*key);
+        }
+    }
+    keys
+}
+
+// Serializes the accounts in the loader's input layout (see entrypoint::deserialize).
+// Returns the u64-backed buffer and the byte offset of each unique account's record.
+fn serialize(
+    program_id: &Pubkey,
+    metas: &[(Pubkey, bool, bool)],
+    keys: &[Pubkey],
+    states: &[AccountState],
+    data: &[u8],
+) -> (Vec<u64>, Vec<usize>) {
+    let mut bytes = Vec::new();
+    let mut offsets = vec![0; keys.len()];
+    bytes.extend_from_slice(&(metas.len() as u64).to_le_bytes());
+    for (position, (key, _, _)) in metas.iter().enumerate() {
+        let first = metas.iter().position(|(other, _, _)| other == key).unwrap();
+        if first != position {
+            bytes.push(first as u8);
+            bytes.extend_from_slice(&[0; 7]);
+            continue;
+        }
+        let index = keys.iter().position(|other| other == key).unwrap();
+        let state = &states[index];
+        // Privileges of duplicates are merged, as when a message is compiled
+        let is_signer = metas
+            .iter()
+            .any(|(other, signer, _)| other == key && *signer);
+        let is_writable = metas
+            .iter()
+            .any(|(other, _, writable)| other == key && *writable);
+        offsets[index] = bytes.len();
+        bytes.push(NON_DUP_MARKER);
+        bytes.push(is_signer as u8);
+        bytes.push(is_writable as u8);
+        bytes.push(state.executable as u8);
+        bytes.extend_from_slice(&[0; 4]); // original_data_len, written by deserialize
+        bytes.extend_from_slice(key.as_ref());
+        bytes.extend_from_slice(state.owner.as_ref());
+        bytes.extend_from_slice(&state.lamports.to_le_bytes());
+        bytes.extend_from_slice(&(state.data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&state.data);
+        bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        bytes.resize(bytes.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes()); // rent_epoch
+    }
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(data);
+    bytes.extend_from_slice(program_id.as_ref());
+
+    let mut buffer = vec![0u64; bytes.len().div_ceil(8)];
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer.as_mut_ptr() as *mut u8, bytes.len());
+    }
+    (buffer, offsets)
+}
+
+// Reads an account back from its serialized record after the program ran
+fn read_account(buffer: &[u8], offset: usize) -> AccountState {
+    let u64_at = |at: usize| u64::from_le_bytes(buffer[at..at + 8].try_into().unwrap());
+    let data_len = u64_at(offset + 80) as usize;
+    AccountState {
+        executable: buffer[offset + 3] != 0,
+        owner: Pubkey::try_from(&buffer[offset + 40..offset + 72]).unwrap(),
+        lamports: u64_at(offset + 72),
+        data: buffer[offset + 88..offset + 88 + data_len].to_vec(),
+    }
+}
+
+fn dispatch(
+    program_id: &Pubkey,
+    accounts: &'static [AccountInfo<'static>],
+    data: &[u8],
+) -> ProgramResult {
+    if *program_id == intel_market::ID {
+        intel_market::entry(program_id, accounts, data)
+    } else if *program_id == spl_token::ID {
+        spl_token::processor::Processor::process(program_id, accounts, data)
+    } else if *program_id == spl_associated_token_account::ID {
+        spl_associated_token_account::processor::process_instruction(program_id, accounts, data)
+    } else if *program_id == system_program::ID {
+        process_system_instruction(accounts, data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+// Runs one instruction over `pre` (one state per unique key) and checks what the program
+// did against the runtime's rules. Returns the post states in the same order.
+fn execute(
+    program_id: &Pubkey,
+    metas: &[(Pubkey, bool, bool)],
+    keys: &[Pubkey],
+    pre: &[AccountState],
+    data: &[u8],
+) -> std::result::Result<Vec<AccountState>, ProgramError> {
+    let (mut buffer, offsets) = serialize(program_id, metas, keys, pre, data);
+    let input = buffer.as_mut_ptr() as *mut u8;
+    // The infos borrow the buffer for the program's lifetime parameter; they are leaked and
+    // never touched again once the buffer is read back and dropped below
+    let (program_id, infos, data) = unsafe { deserialize::<'static>(input) };
+    let infos: &'static [AccountInfo<'static>] = Box::leak(infos.into_boxed_slice());
+    with_invocation(|invocation| {
+        invocation.stack.push(Frame {
+            program_id: *program_id,
+            metas: metas.to_vec(),
+            baseline: keys.iter().copied().zip(pre.iter().cloned()).collect(),
+        });
+        invocation.return_data = None;
+    });
+    let result = dispatch(program_id, infos, data);
+    let frame = with_invocation(|invocation| invocation.stack.pop().unwrap());
+    result?;
+
+    let bytes =
+        unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * 8) };
+    let post: Vec<AccountState> = offsets
+        .iter()
+        .map(|offset| read_account(bytes, *offset))
+        .collect();
+    let before: Vec<AccountState> = keys.iter().map(|key| frame.baseline[key].clone()).collect();
+    verify(program_id, metas, keys, &before, &post, true).map_err(violation)?;
+    Ok(post)
+}
+
+// The subset of the runtime's account checks the tests rely on
+fn verify(
+    program_id: &Pubkey,
+    metas: &[(Pubkey, bool, bool)],
+    keys: &[Pubkey],
+    pre: &[AccountState],
+    post: &[AccountState],
+    conserve: bool,
+) -> std::result::Result<(), String> {
+    for ((key, before), after) in keys.iter().zip(pre).zip(post) {
+        if before == after {
+            continue;
+        }
+        let writable = metas
+            .iter()
+            .any(|(other, _, writable)| other == key && *writable);
+        if !writable {
+            return Err(format!("{program_id} modified read-only account {key}"));
+        }
+        if before.executable || after.executable {
+            return Err(format!("{program_id} modified executable account {key}"));
+        }
+        let owned = before.owner == *program_id;
+        if !owned && before.data != after.data {
+            return Err(format!(
+                "{program_id} modified data of external account {key}"
+            ));
+        }
+        if !owned && after.lamports < before.lamports {
+            return Err(format!("{program_id} debited external account {key}"));
+        }
+        if before.owner != after.owner && (!owned || after.data.iter().any(|byte| *byte != 0)) {
+            return Err(format!("{program_id} illegally changed the owner of {key}"));
+        }
+    }
+    let total = |states: &[AccountState]| states.iter().map(|s| s.lamports as u128).sum::<u128>();
+    if conserve && total(pre) != total(post) {
+        return Err(format!("{program_id} did not conserve lamports"));
+    }
+    Ok(())
+}
+
+// Transaction-level rent rule: an account may only end up rent-paying if it already was,
+// with the same size and no more lamports than before
+fn check_rent_state(
+    key: &Pubkey,
+    before: &AccountState,
+    after: &AccountState,
+) -> std::result::Result<(), String> {
+    let rent = Rent::default();
+    let paying = |state: &AccountState| {
+        state.lamports > 0 && state.lamports < rent.minimum_balance(state.data.len())
+    };
+    if paying(after)
+        && !(paying(before)
+            && before.data.len() == after.data.len()
+            && after.lamports <= before.lamports)
+    {
+        return Err(format!("insufficient funds for rent: {key}"));
+    }
+    Ok(())
+}
+
+fn process_system_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction =
+        bincode::deserialize(data).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let account = |index: usize| {
+        accounts
+            .get(index)
+            .ok_or(ProgramError::NotEnoughAccountKeys)
+    };
+    let require_signer = |info: &AccountInfo| {
+        if info.is_signer {
+            Ok(())
+        } else {
+            Err(ProgramError::MissingRequiredSignature)
+        }
+    };
+    let debit = |from: &AccountInfo, to: &AccountInfo, lamports: u64| {
+        if from.lamports() < lamports {
+            return Err(ProgramError::Custom(1)); // SystemError::ResultWithNegativeLamports
+        }
+        **from.try_borrow_mut_lamports()? -= lamports;
+        **to.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    };
+    let allocate = |info: &AccountInfo, space: u64| {
+        require_signer(info)?;
+        if !info.data_is_empty() || *info.owner != system_program::ID {
+            return Err(ProgramError::Custom(0)); // SystemError::AccountAlreadyInUse
+        }
+        info.realloc(space as usize, true)
+    };
+
+    match instruction {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let (from, to) = (account(0)?, account(1)?);
+            require_signer(from)?;
+            if to.lamports() > 0 {
+                return Err(ProgramError::Custom(0));
+            }
+            allocate(to, space)?;
+            to.assign(&owner);
+            debit(from, to, lamports)
+        }
+        SystemInstruction::Assign { owner } => {
+            let info = account(0)?;
+            require_signer(info)?;
+            info.assign(&owner);
+            Ok(())
+        }
+        SystemInstruction::Transfer { lamports } => {
+            let (from, to) = (account(0)?, account(1)?);
+            require_signer(from)?;
+            if !from.data_is_empty() {
+                return Err(ProgramError::InvalidArgument);
+            }
+            debit(from, to, lamports)
+        }
+        SystemInstruction::Allocate { space } => allocate(account(0)?, space),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+pub struct Runtime {
+    accounts: HashMap<Pubkey, AccountState>,
+    pub clock: Clock,
+    pub upgrade_authority: Pubkey, // Upgrade authority of the intel_market program
+    logs: Vec<String>,
+    events: Vec<Vec<u8>>,
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut runtime = Runtime {
+            accounts: HashMap::new(),
+            clock: Clock {
+                unix_timestamp: GENESIS_TIMESTAMP,
+                ..Clock::default()
+            },
+            upgrade_authority: Pubkey::new_unique(),
+            logs: Vec::new(),
+            events: Vec::new(),
+        };
+        let native_loader = "NativeLoader1111111111111111111111111111111"
+            .parse()
+            .unwrap();
+        runtime.add_program(system_program::ID, native_loader, Vec::new());
+        runtime.add_program(spl_token::ID, bpf_loader::ID, Vec::new());
+        runtime.add_program(spl_associated_token_account::ID, bpf_loader::ID, Vec::new());
+
+        let (program_data, _) =
+            Pubkey::find_program_address(&[intel_market::ID.as_ref()], &bpf_loader_upgradeable::ID);
+        let program = UpgradeableLoaderState::Program {
+            programdata_address: program_data,
+        };
+        runtime.add_program(
+            intel_market::ID,
+            bpf_loader_upgradeable::ID,
+            bincode::serialize(&program).unwrap(),
+        );
+        let data = UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(runtime.upgrade_authority),
+        };
+        runtime.set_account(
+            program_data,
+            AccountState {
+                lamports: LAMPORTS_PER_SOL,
+                data: bincode::serialize(&data).unwrap(),
+                owner: bpf_loader_upgradeable::ID,
+                executable: false,
+            },
+        );
+        runtime.airdrop(&runtime.upgrade_authority.clone(), 10 * LAMPORTS_PER_SOL);
+        runtime
+    }
+
+    fn add_program(&mut self, id: Pubkey, loader: Pubkey, data: Vec<u8>) {
+        self.set_account(
+            id,
+            AccountState {
+                lamports: LAMPORTS_PER_SOL,
+                data,
+                owner: loader,
+                executable: true,
+            },
+        );
+    }
+
+    // A new system account holding `lamports`
+    pub fn wallet(&mut self, lamports: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.airdrop(&key, lamports);
+        key
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        let account = self.accounts.entry(*key).or_default();
+        account.lamports += lamports;
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: AccountState) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&AccountState> {
+        self.accounts.get(key)
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.account(key).map_or(0, |account| account.lamports)
+    }
+
+    // Deserializes an Anchor account, panicking if it is missing or malformed
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self
+            .account(key)
+            .unwrap_or_else(|| panic!("account {key} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub fn warp(&mut self, seconds: i64) {
+        self.clock.unix_timestamp += seconds;
+        self.clock.slot += (seconds.max(0) as u64) * 2;
+    }
+
+    // Log lines of the last transaction, for debugging failures
+    pub fn logs(&self) -> &[String] {
+        &self.logs
+    }
+
+    // Events emitted by the last transaction
+    pub fn events<E: Event + AnchorDeserialize>(&self) -> Vec<E> {
+        self.events
+            .iter()
+            .filter_map(|data| data.strip_prefix(&E::DISCRIMINATOR))
+            .map(|mut body| E::deserialize(&mut body).unwrap())
+            .collect()
+    }
+
+    // Executes the instructions atomically. `signers` lists the keys that signed.
+    pub fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<(), TxError> {
+        with_invocation(|invocation| {
+            *invocation = Invocation {
+                clock: self.clock.clone(),
+                ..Invocation::default()
+            }
+        });
+        let mut working = self.accounts.clone();
+        let mut touched: HashMap<Pubkey, AccountState> = HashMap::new();
+        let mut result = Ok(());
+        for instruction in instructions {
+            result = self.execute_top_level(instruction, signers, &mut working, &mut touched);
+            if result.is_err() {
+                break;
+            }
+        }
+        if result.is_ok() {
+            result = touched.iter().try_for_each(|(key, before)| {
+                let after = working.get(key).cloned().unwrap_or_default();
+                check_rent_state(key, before, &after).map_err(TxError::Runtime)
+            });
+        }
+
+        let invocation = with_invocation(std::mem::take);
+        self.logs = invocation.logs;
+        self.events = invocation.events;
+        if let Err(TxError::Program(_)) = result {
+            if let Some(message) = invocation.violation {
+                result = Err(TxError::Runtime(message));
+            }
+        }
+        if result.is_ok() {
+            working.retain(|_, account| account.lamports > 0);
+            self.accounts = working;
+        }
+        result
+    }
+
+    fn execute_top_level(
+        &self,
+        instruction: &Instruction,
+        signers: &[Pubkey],
+        working: &mut HashMap<Pubkey, AccountState>,
+        touched: &mut HashMap<Pubkey, AccountState>,
+    ) -> std::result::Result<(), TxError> {
+        match working.get(&instruction.program_id) {
+            Some(program) if program.executable => {}
+            _ => {
+                return Err(TxError::Runtime(format!(
+                    "{} is not a program",
+                    instruction.program_id
+                )))
+            }
+        }
+        let metas: Vec<(Pubkey, bool, bool)> = instruction
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+            .collect();
+        if let Some((key, _, _)) = metas
+            .iter()
+            .find(|(key, signer, _)| *signer && !signers.contains(key))
+        {
+            return Err(TxError::Runtime(format!("missing signature for {key}")));
+        }
+        let keys = unique_keys(&metas);
+        let pre: Vec<AccountState> = keys
+            .iter()
+            .map(|key| {
+                working.get(key).cloned().unwrap_or_else(|| AccountState {
+                    owner: system_program::ID,
+                    ..AccountState::default()
+                })
+            })
+            .collect();
+        for (key, state) in keys.iter().zip(&pre) {
+            touched.entry(*key).or_insert_with(|| state.clone());
+        }
+        let post = execute(
+            &instruction.program_id,
+            &metas,
+            &keys,
+            &pre,
+            &instruction.data,
+        )
+        .map_err(TxError::Program)?;
+        for (key, state) in keys.into_iter().zip(post) {
+            working.insert(key, state);
+        }
+        Ok(())
+    }
+
+    // SPL helpers
+
+    pub fn create_mint(&mut self, authority: &Pubkey, decimals: u8) -> Pubkey {
+        let mint = spl_token::state::Mint {
+            mint_authority: Some(*authority).into(),
+            supply: 0,
+            decimals,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        let key = Pubkey::new_unique();
+        self.set_account(
+            key,
+            AccountState {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+            },
+        );
+        key
+    }
+
+    // Creates `owner`'s associated token account through the ATA program, paid by `payer`
+    pub fn create_associated_token_account(
+        &mut self,
+        payer: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+    ) -> Pubkey {
+        let instruction =
+            spl_associated_token_account::instruction::create_associated_token_account(
+                payer,
+                owner,
+                mint,
+                &spl_token::ID,
+            );
+        self.send(&[instruction], &[*payer]).unwrap();
+        anchor_spl::associated_token::get_associated_token_address(owner, mint)
+    }
+
+    pub fn mint_to(&mut self, mint: &Pubkey, authority: &Pubkey, to: &Pubkey, amount: u64) {
+        let instruction =
+            spl_token::instruction::mint_to(&spl_token::ID, mint, to, authority, &[], amount)
+                .unwrap();
+        self.send(&[instruction], &[*authority]).unwrap();
+    }
+
+    pub fn token_balance(&self, token_account: &Pubkey) -> u64 {
+        let account = self
+            .account(token_account)
+            .unwrap_or_else(|| panic!("token account {token_account} does not exist"));
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+}
@@ -0,0 +1,85 @@
+// Instruction-level tests of the program config and pause.
+
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use intel_market::{
+    accounts, instruction, FeeSchedule, IntelMarketError, LocationPrecision, ProgramConfig,
+};
+
+#[test]
+fn only_upgrade_authority_initializes_config() {
+    let mut runtime = Runtime::new();
+    let squatter = runtime.wallet(LAMPORTS_PER_SOL);
+    assert_eq!(
+        initialize_config(&mut runtime, &squatter, 0, None),
+        Err(program_error(IntelMarketError::Unauthorized))
+    );
+    assert!(runtime.account(&config_pda()).is_none());
+
+    let authority = runtime.upgrade_authority;
+    initialize_config(&mut runtime, &authority, 100, Some(squatter)).unwrap();
+    let config = runtime.get::<ProgramConfig>(&config_pda());
+    assert_eq!(config.admin, authority);
+    assert_eq!(config.fee_bps, 100);
+}
+
+#[test]
+fn pause_stops_user_instructions_but_not_the_admin() {
+    let mut runtime = Runtime::new();
+    let market = setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let buyer = runtime.wallet(LAMPORTS_PER_SOL);
+    let report = create_report(&mut runtime, &author);
+    list(
+        &mut runtime,
+        &author,
+        &report,
+        LAMPORTS_PER_SOL / 10,
+        vec![],
+    )
+    .unwrap();
+    let team = create_team(&mut runtime, &author);
+
+    assert_eq!(
+        set_paused(&mut runtime, &author, true).unwrap_err(),
+        anchor_lang::error::Error::from(ErrorCode::ConstraintHasOne).into()
+    );
+    set_paused(&mut runtime, &market.admin, true).unwrap();
+    let paused = program_error(IntelMarketError::ProgramPaused);
+    assert_eq!(
+        create_report_at(
+            &mut runtime,
+            &author,
+            48_850_000,
+            2_350_000,
+            LocationPrecision::City
+        )
+        .unwrap_err(),
+        paused
+    );
+    assert_eq!(purchase(&mut runtime, &buyer, &report).unwrap_err(), paused);
+    assert_eq!(join(&mut runtime, &team, &buyer).unwrap_err(), paused);
+
+    // The admin can still change the config, and lift the pause
+    let update = ix(
+        accounts::UpdateConfig {
+            config: config_pda(),
+            admin: market.admin,
+        },
+        instruction::UpdateConfig {
+            new_admin: market.admin,
+            fee_bps: 100,
+            fee_recipient: Some(market.fee_recipient),
+            max_content_len: 1_000,
+            fees: FeeSchedule::default(),
+        },
+    );
+    runtime.send(&[update], &[market.admin]).unwrap();
+    let config = runtime.get::<ProgramConfig>(&config_pda());
+    assert!(config.paused);
+    assert_eq!((config.fee_bps, config.max_content_len), (100, 1_000));
+    set_paused(&mut runtime, &market.admin, false).unwrap();
+    purchase(&mut runtime, &buyer, &report).unwrap();
+}
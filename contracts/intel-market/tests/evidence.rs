@@ -0,0 +1,90 @@
+// Instruction-level tests of evidence: who may attach it and the chain of custody.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use common::*;
+use intel_market::{Evidence, EvidenceCustodyTransferred, IntelMarketError, TeamRole};
+
+#[test]
+fn voting_members_attach_evidence_to_team_reports() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let owner = runtime.wallet(LAMPORTS_PER_SOL);
+    let team = create_team(&mut runtime, &owner);
+    let analyst = add_member(&mut runtime, &team, &owner, TeamRole::Analyst);
+    let observer = add_member(&mut runtime, &team, &owner, TeamRole::Observer);
+    let outsider = runtime.wallet(LAMPORTS_PER_SOL);
+    let team_report = NewReport {
+        team: Some(team),
+        ..NewReport::default()
+    };
+    let report = create_report_with(&mut runtime, &owner, team_report).unwrap();
+
+    assert_eq!(
+        attach_evidence(&mut runtime, &outsider, &report, [1; 32]).unwrap_err(),
+        program_error(IntelMarketError::NotTeamMember)
+    );
+    assert_eq!(
+        attach_evidence(&mut runtime, &observer, &report, [1; 32]).unwrap_err(),
+        program_error(IntelMarketError::Unauthorized)
+    );
+    let evidence = attach_evidence(&mut runtime, &analyst, &report, [1; 32]).unwrap();
+    let state = runtime.get::<Evidence>(&evidence);
+    assert_eq!(state.intel_report, report);
+    assert_eq!((state.collected_by, state.custodian), (analyst, analyst));
+    assert_eq!(state.custody_transfers, 0);
+
+    // Reports outside a team only take evidence from their author
+    let private = create_report(&mut runtime, &owner);
+    assert_eq!(
+        attach_evidence(&mut runtime, &analyst, &private, [2; 32]).unwrap_err(),
+        program_error(IntelMarketError::Unauthorized)
+    );
+    attach_evidence(&mut runtime, &owner, &private, [2; 32]).unwrap();
+}
+
+// Hand-offs emitted by the last transaction, as (from, to, transfer number)
+fn custody_events(runtime: &Runtime) -> Vec<(Pubkey, Pubkey, u32)> {
+    runtime
+        .events::<EvidenceCustodyTransferred>()
+        .iter()
+        .map(|transfer| (transfer.from, transfer.to, transfer.transfer))
+        .collect()
+}
+
+#[test]
+fn only_the_custodian_hands_evidence_on() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let lab = runtime.wallet(LAMPORTS_PER_SOL);
+    let court = runtime.wallet(LAMPORTS_PER_SOL);
+    let report = create_report(&mut runtime, &author);
+    let evidence = attach_evidence(&mut runtime, &author, &report, [7; 32]).unwrap();
+    let not_custodian = program_error(IntelMarketError::NotCustodian);
+
+    assert_eq!(
+        transfer_custody(&mut runtime, &evidence, &lab, &court).unwrap_err(),
+        not_custodian
+    );
+    runtime.warp(3_600);
+    transfer_custody(&mut runtime, &evidence, &author, &lab).unwrap();
+    let handed_at = runtime.clock.unix_timestamp;
+    let mut chain = custody_events(&runtime);
+    // The previous custodian no longer holds it
+    assert_eq!(
+        transfer_custody(&mut runtime, &evidence, &author, &court).unwrap_err(),
+        not_custodian
+    );
+    runtime.warp(3_600);
+    transfer_custody(&mut runtime, &evidence, &lab, &court).unwrap();
+
+    let state = runtime.get::<Evidence>(&evidence);
+    assert_eq!(state.collected_by, author);
+    assert_eq!(state.custodian, court);
+    assert_eq!(state.custody_transfers, 2);
+    assert_eq!(state.last_transfer_at, handed_at + 3_600);
+    chain.extend(custody_events(&runtime));
+    assert_eq!(chain, vec![(author, lab, 1), (lab, court, 2)]);
+}
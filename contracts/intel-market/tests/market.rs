@@ -0,0 +1,460 @@
+// Instruction-level tests of the report market: listing, escrowed purchases, settlement,
+// disputes, cancellation and ratings, for both SOL and SPL listings.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use common::*;
+use intel_market::{
+    AccessStatus, AuthorReputation, DisputeOutcome, IntelMarketError, Listing, ReportAccess,
+    ReportRating, RevenueShare,
+};
+
+const PRICE: u64 = LAMPORTS_PER_SOL;
+const TOKEN_PRICE: u64 = 40_000_000;
+
+fn fee(amount: u64) -> u64 {
+    amount * FEE_BPS as u64 / 10_000
+}
+
+// A market with one listed report, its seller and a funded buyer
+fn listed(runtime: &mut Runtime) -> (Market, Pubkey, Pubkey, Pubkey) {
+    let market = setup(runtime);
+    let seller = runtime.wallet(LAMPORTS_PER_SOL);
+    let buyer = runtime.wallet(10 * LAMPORTS_PER_SOL);
+    let report = create_report(runtime, &seller);
+    list(runtime, &seller, &report, PRICE, vec![]).unwrap();
+    (market, seller, buyer, report)
+}
+
+#[test]
+fn purchase_escrows_payment_until_buyer_confirms() {
+    let mut runtime = Runtime::new();
+    let (market, seller, buyer, report) = listed(&mut runtime);
+    let listing = listing_pda(&report);
+    let listing_rent = runtime.lamports(&listing);
+    let buyer_before = runtime.lamports(&buyer);
+
+    purchase(&mut runtime, &buyer, &report).unwrap();
+    let access = access_pda(&report, &buyer);
+    let access_rent = runtime.lamports(&access);
+    assert_eq!(runtime.lamports(&listing), listing_rent + PRICE);
+    assert_eq!(runtime.lamports(&buyer), buyer_before - PRICE - access_rent);
+    assert_eq!(runtime.get::<Listing>(&listing).buyer, Some(buyer));
+    assert_eq!(
+        runtime.get::<ReportAccess>(&access).status,
+        AccessStatus::Pending
+    );
+
+    let seller_before = runtime.lamports(&seller);
+    let fees_before = runtime.lamports(&market.fee_recipient);
+    settle(&mut runtime, &market, &buyer, &report, &buyer, &[]).unwrap();
+    assert_eq!(runtime.lamports(&listing), listing_rent);
+    assert_eq!(
+        runtime.lamports(&market.fee_recipient),
+        fees_before + fee(PRICE)
+    );
+    assert_eq!(
+        runtime.lamports(&seller),
+        seller_before + PRICE - fee(PRICE)
+    );
+    assert_eq!(
+        runtime.get::<ReportAccess>(&access).status,
+        AccessStatus::Granted
+    );
+    let listing_state = runtime.get::<Listing>(&listing);
+    assert_eq!(listing_state.buyer, None);
+    assert_eq!(listing_state.sales, 1);
+}
+
+#[test]
+fn seller_settles_only_after_hold_period() {
+    let mut runtime = Runtime::new();
+    let (market, seller, buyer, report) = listed(&mut runtime);
+    purchase(&mut runtime, &buyer, &report).unwrap();
+
+    assert_eq!(
+        settle(&mut runtime, &market, &seller, &report, &buyer, &[]),
+        Err(program_error(IntelMarketError::EscrowHoldActive))
+    );
+    let stranger = runtime.wallet(LAMPORTS_PER_SOL);
+    assert_eq!(
+        settle(&mut runtime, &market, &stranger, &report, &buyer, &[]),
+        Err(program_error(IntelMarketError::Unauthorized))
+    );
+
+    runtime.warp(Listing::ESCROW_HOLD_SECS);
+    let seller_before = runtime.lamports(&seller);
+    settle(&mut runtime, &market, &seller, &report, &buyer, &[]).unwrap();
+    assert_eq!(
+        runtime.lamports(&seller),
+        seller_before + PRICE - fee(PRICE)
+    );
+}
+
+#[test]
+fn settlement_pays_revenue_shares() {
+    let mut runtime = Runtime::new();
+    let market = setup(&mut runtime);
+    let seller = runtime.wallet(LAMPORTS_PER_SOL);
+    let co_author = runtime.wallet(LAMPORTS_PER_SOL);
+    let buyer = runtime.wallet(10 * LAMPORTS_PER_SOL);
+    let report = create_report(&mut runtime, &seller);
+    let shares = vec![
+        RevenueShare {
+            recipient: seller,
+            bps: 7_000,
+        },
+        RevenueShare {
+            recipient: co_author,
+            bps: 3_000,
+        },
+    ];
+    list(&mut runtime, &seller, &report, PRICE, shares).unwrap();
+    purchase(&mut runtime, &buyer, &report).unwrap();
+
+    assert_eq!(
+        settle(&mut runtime, &market, &buyer, &report, &buyer, &[seller]),
+        Err(program_error(
+            IntelMarketError::RevenueShareAccountsMismatch
+        ))
+    );
+    let seller_before = runtime.lamports(&seller);
+    let co_author_before = runtime.lamports(&co_author);
+    settle(
+        &mut runtime,
+        &market,
+        &buyer,
+        &report,
+        &buyer,
+        &[seller, co_author],
+    )
+    .unwrap();
+    let net = PRICE - fee(PRICE);
+    assert_eq!(
+        runtime.lamports(&co_author),
+        co_author_before + net * 3 / 10
+    );
+    assert_eq!(
+        runtime.lamports(&seller),
+        seller_before + net - net * 3 / 10
+    );
+}
+
+#[test]
+fn cancel_refunds_pending_buyer() {
+    let mut runtime = Runtime::new();
+    let (_, seller, buyer, report) = listed(&mut runtime);
+    let buyer_before = runtime.lamports(&buyer);
+    purchase(&mut runtime, &buyer, &report).unwrap();
+
+    assert_eq!(
+        cancel(&mut runtime, &seller, &report, None),
+        Err(program_error(IntelMarketError::BuyerAccountMissing))
+    );
+    let listing = listing_pda(&report);
+    let seller_before = runtime.lamports(&seller);
+    let listing_rent = runtime.lamports(&listing) - PRICE;
+    cancel(&mut runtime, &seller, &report, Some(&buyer)).unwrap();
+    assert!(runtime.account(&listing).is_none());
+    assert!(runtime.account(&access_pda(&report, &buyer)).is_none());
+    assert_eq!(runtime.lamports(&buyer), buyer_before);
+    assert_eq!(runtime.lamports(&seller), seller_before + listing_rent);
+}
+
+#[test]
+fn dispute_refund_returns_escrow_and_counts_against_author() {
+    let mut runtime = Runtime::new();
+    let (market, seller, buyer, report) = listed(&mut runtime);
+    initialize_reputation(&mut runtime, &buyer, &seller);
+    let buyer_before = runtime.lamports(&buyer);
+    purchase(&mut runtime, &buyer, &report).unwrap();
+
+    assert_eq!(
+        resolve_dispute(
+            &mut runtime,
+            &market,
+            &report,
+            &buyer,
+            DisputeOutcome::Refund
+        ),
+        Err(program_error(IntelMarketError::NoActiveDispute))
+    );
+    open_dispute(&mut runtime, &buyer, &report).unwrap();
+    runtime.warp(Listing::ESCROW_HOLD_SECS);
+    assert_eq!(
+        settle(&mut runtime, &market, &seller, &report, &buyer, &[]),
+        Err(program_error(IntelMarketError::DisputeActive))
+    );
+
+    resolve_dispute(
+        &mut runtime,
+        &market,
+        &report,
+        &buyer,
+        DisputeOutcome::Refund,
+    )
+    .unwrap();
+    assert_eq!(runtime.lamports(&buyer), buyer_before);
+    assert_eq!(
+        runtime
+            .get::<AuthorReputation>(&reputation_pda(&seller))
+            .disputes,
+        1
+    );
+    assert_eq!(runtime.get::<Listing>(&listing_pda(&report)).buyer, None);
+}
+
+#[test]
+fn dispute_release_pays_seller() {
+    let mut runtime = Runtime::new();
+    let (market, seller, buyer, report) = listed(&mut runtime);
+    initialize_reputation(&mut runtime, &buyer, &seller);
+    purchase(&mut runtime, &buyer, &report).unwrap();
+    open_dispute(&mut runtime, &buyer, &report).unwrap();
+
+    let seller_before = runtime.lamports(&seller);
+    resolve_dispute(
+        &mut runtime,
+        &market,
+        &report,
+        &buyer,
+        DisputeOutcome::Release,
+    )
+    .unwrap();
+    assert_eq!(
+        runtime.lamports(&seller),
+        seller_before + PRICE - fee(PRICE)
+    );
+    assert_eq!(
+        runtime
+            .get::<ReportAccess>(&access_pda(&report, &buyer))
+            .status,
+        AccessStatus::Granted
+    );
+}
+
+// A market with one report listed for tokens; seller, buyer and fee recipient hold
+// token accounts, the buyer funded with twice the price
+fn listed_spl(runtime: &mut Runtime) -> (Market, Pubkey, Pubkey, Pubkey, Pubkey) {
+    let market = setup(runtime);
+    let seller = runtime.wallet(LAMPORTS_PER_SOL);
+    let buyer = runtime.wallet(LAMPORTS_PER_SOL);
+    let mint = token_setup(
+        runtime,
+        &[
+            (seller, 0),
+            (buyer, 2 * TOKEN_PRICE),
+            (market.fee_recipient, 0),
+        ],
+    );
+    let report = create_report(runtime, &seller);
+    list_spl(runtime, &seller, &report, &mint, TOKEN_PRICE).unwrap();
+    (market, seller, buyer, report, mint)
+}
+
+#[test]
+fn spl_purchase_settles_through_escrow_token_account() {
+    let mut runtime = Runtime::new();
+    let (market, seller, buyer, report, mint) = listed_spl(&mut runtime);
+    let escrow = get_associated_token_address(&listing_pda(&report), &mint);
+    assert_eq!(runtime.token_balance(&escrow), 0);
+
+    purchase_spl(&mut runtime, &buyer, &report, &mint).unwrap();
+    let buyer_token = get_associated_token_address(&buyer, &mint);
+    assert_eq!(runtime.token_balance(&escrow), TOKEN_PRICE);
+    assert_eq!(runtime.token_balance(&buyer_token), TOKEN_PRICE);
+
+    settle_spl(&mut runtime, &market, &buyer, &report, &buyer, &mint).unwrap();
+    assert_eq!(runtime.token_balance(&escrow), 0);
+    assert_eq!(
+        runtime.token_balance(&get_associated_token_address(&seller, &mint)),
+        TOKEN_PRICE - fee(TOKEN_PRICE)
+    );
+    assert_eq!(
+        runtime.token_balance(&get_associated_token_address(&market.fee_recipient, &mint)),
+        fee(TOKEN_PRICE)
+    );
+}
+
+#[test]
+fn spl_cancel_refunds_buyer_and_closes_escrow() {
+    let mut runtime = Runtime::new();
+    let (_, seller, buyer, report, mint) = listed_spl(&mut runtime);
+    purchase_spl(&mut runtime, &buyer, &report, &mint).unwrap();
+
+    let escrow = get_associated_token_address(&listing_pda(&report), &mint);
+    let escrow_rent = runtime.lamports(&escrow);
+    let listing_rent = runtime.lamports(&listing_pda(&report));
+    let seller_before = runtime.lamports(&seller);
+    cancel_spl(&mut runtime, &seller, &report, &mint, Some(&buyer)).unwrap();
+    assert!(runtime.account(&escrow).is_none());
+    assert!(runtime.account(&listing_pda(&report)).is_none());
+    assert_eq!(
+        runtime.token_balance(&get_associated_token_address(&buyer, &mint)),
+        2 * TOKEN_PRICE
+    );
+    assert_eq!(
+        runtime.lamports(&seller),
+        seller_before + escrow_rent + listing_rent
+    );
+}
+
+#[test]
+fn spl_dispute_refund_returns_tokens() {
+    let mut runtime = Runtime::new();
+    let (market, seller, buyer, report, mint) = listed_spl(&mut runtime);
+    initialize_reputation(&mut runtime, &buyer, &seller);
+    purchase_spl(&mut runtime, &buyer, &report, &mint).unwrap();
+    open_dispute(&mut runtime, &buyer, &report).unwrap();
+
+    resolve_dispute_spl(
+        &mut runtime,
+        &market,
+        &report,
+        &buyer,
+        &mint,
+        DisputeOutcome::Refund,
+    )
+    .unwrap();
+    assert_eq!(
+        runtime.token_balance(&get_associated_token_address(&buyer, &mint)),
+        2 * TOKEN_PRICE
+    );
+    assert!(runtime.account(&access_pda(&report, &buyer)).is_none());
+    assert_eq!(
+        runtime
+            .get::<AuthorReputation>(&reputation_pda(&seller))
+            .disputes,
+        1
+    );
+}
+
+#[test]
+fn dispute_resolves_without_reputation_account() {
+    let mut runtime = Runtime::new();
+    let (market, _, buyer, report) = listed(&mut runtime);
+    let buyer_before = runtime.lamports(&buyer);
+    purchase(&mut runtime, &buyer, &report).unwrap();
+    open_dispute(&mut runtime, &buyer, &report).unwrap();
+
+    resolve_dispute(
+        &mut runtime,
+        &market,
+        &report,
+        &buyer,
+        DisputeOutcome::Refund,
+    )
+    .unwrap();
+    assert_eq!(runtime.lamports(&buyer), buyer_before);
+}
+
+#[test]
+fn listed_report_is_frozen_until_cancelled() {
+    let mut runtime = Runtime::new();
+    let (_, seller, buyer, report) = listed(&mut runtime);
+    purchase(&mut runtime, &buyer, &report).unwrap();
+
+    assert_eq!(
+        update_title(&mut runtime, &seller, &report, "Bait"),
+        Err(program_error(IntelMarketError::ReportListed))
+    );
+    assert_eq!(
+        retract(&mut runtime, &seller, &report),
+        Err(program_error(IntelMarketError::ReportListed))
+    );
+
+    cancel(&mut runtime, &seller, &report, Some(&buyer)).unwrap();
+    update_title(&mut runtime, &seller, &report, "Beaconing host (corrected)").unwrap();
+    retract(&mut runtime, &seller, &report).unwrap();
+    assert!(runtime.account(&report).is_none());
+}
+
+#[test]
+fn superseded_report_cannot_be_bought() {
+    let mut runtime = Runtime::new();
+    let (_, seller, buyer, report) = listed(&mut runtime);
+    supersede(&mut runtime, &seller, &report);
+
+    assert_eq!(
+        purchase(&mut runtime, &buyer, &report),
+        Err(program_error(IntelMarketError::ReportSuperseded))
+    );
+}
+
+#[test]
+fn spl_cancel_sweeps_tokens_sent_to_escrow() {
+    let mut runtime = Runtime::new();
+    let (_, seller, buyer, report, mint) = listed_spl(&mut runtime);
+    purchase_spl(&mut runtime, &buyer, &report, &mint).unwrap();
+
+    // Anyone can top up the escrow; it must not keep the listing from closing
+    let escrow = get_associated_token_address(&listing_pda(&report), &mint);
+    let buyer_token = get_associated_token_address(&buyer, &mint);
+    let donation =
+        spl_token::instruction::transfer(&spl_token::ID, &buyer_token, &escrow, &buyer, &[], 1)
+            .unwrap();
+    runtime.send(&[donation], &[buyer]).unwrap();
+
+    cancel_spl(&mut runtime, &seller, &report, &mint, Some(&buyer)).unwrap();
+    assert!(runtime.account(&escrow).is_none());
+    assert_eq!(runtime.token_balance(&buyer_token), 2 * TOKEN_PRICE - 1);
+    assert_eq!(
+        runtime.token_balance(&get_associated_token_address(&seller, &mint)),
+        1
+    );
+}
+
+#[test]
+fn revenue_share_below_rent_is_rejected_at_listing() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let seller = runtime.wallet(LAMPORTS_PER_SOL);
+    let report = create_report(&mut runtime, &seller);
+    let minimum = Rent::default().minimum_balance(0);
+    let shares = |co_author_bps| {
+        vec![
+            RevenueShare {
+                recipient: seller,
+                bps: 10_000 - co_author_bps,
+            },
+            // Never funded: the share would have to create the account
+            RevenueShare {
+                recipient: Pubkey::new_unique(),
+                bps: co_author_bps,
+            },
+        ]
+    };
+
+    assert_eq!(
+        list(&mut runtime, &seller, &report, 4 * minimum, shares(2_000)),
+        Err(program_error(IntelMarketError::RevenueShareBelowRent))
+    );
+    list(&mut runtime, &seller, &report, 4 * minimum, shares(5_000)).unwrap();
+}
+
+#[test]
+fn only_settled_buyers_rate_once() {
+    let mut runtime = Runtime::new();
+    let (market, seller, buyer, report) = listed(&mut runtime);
+    initialize_reputation(&mut runtime, &buyer, &seller);
+    purchase(&mut runtime, &buyer, &report).unwrap();
+
+    assert_eq!(
+        rate(&mut runtime, &buyer, &report, 4).unwrap_err(),
+        program_error(IntelMarketError::AccessNotGranted)
+    );
+    settle(&mut runtime, &market, &buyer, &report, &buyer, &[]).unwrap();
+    assert_eq!(
+        rate(&mut runtime, &buyer, &report, ReportRating::MAX_SCORE + 1).unwrap_err(),
+        program_error(IntelMarketError::InvalidRating)
+    );
+    rate(&mut runtime, &buyer, &report, 4).unwrap();
+    let rating = runtime.get::<ReportRating>(&rating_pda(&report, &buyer));
+    assert_eq!((rating.rater, rating.score), (buyer, 4));
+    let reputation = runtime.get::<AuthorReputation>(&reputation_pda(&seller));
+    assert_eq!((reputation.rating_count, reputation.score_sum), (1, 4));
+    assert!(rate(&mut runtime, &buyer, &report, 5).is_err());
+}
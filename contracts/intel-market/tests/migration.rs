@@ -0,0 +1,205 @@
+// Instruction-level tests of account versioning: accounts in the layout the program was
+// first deployed with are rejected until migrated.
+
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use common::*;
+use intel_market::{
+    geohash_encode, AccountMigrated, CyberTeam, CyberTeamV1, IntelMarketError, IntelReport,
+    IntelReportV1, LocationPrecision, ReportContent, TeamMembership, TeamRole,
+};
+
+// Rewrites `key` with `data`, keeping its owner and lamports
+fn overwrite(runtime: &mut Runtime, key: &Pubkey, data: Vec<u8>) {
+    let mut account = runtime.account(key).unwrap().clone();
+    account.data = data;
+    runtime.set_account(*key, account);
+}
+
+// A program-owned keypair account holding `data` padded to `space`, as the first
+// deployment allocated its reports and teams
+fn baseline_account(runtime: &mut Runtime, mut data: Vec<u8>, space: usize) -> Pubkey {
+    let key = Pubkey::new_unique();
+    data.resize(space, 0);
+    runtime.set_account(
+        key,
+        AccountState {
+            lamports: LAMPORTS_PER_SOL / 50,
+            data,
+            owner: intel_market::ID,
+            executable: false,
+        },
+    );
+    key
+}
+
+// An IntelReport written field by field in the original layout
+fn baseline_report(runtime: &mut Runtime, author: &Pubkey, tags: &[&str]) -> Pubkey {
+    let mut data = IntelReport::DISCRIMINATOR.to_vec();
+    "Relay node".to_string().serialize(&mut data).unwrap();
+    "Seen forwarding beacons"
+        .to_string()
+        .serialize(&mut data)
+        .unwrap();
+    let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+    tags.serialize(&mut data).unwrap();
+    48.856_613f64.serialize(&mut data).unwrap();
+    2.352_222f64.serialize(&mut data).unwrap();
+    1_700_000_000i64.serialize(&mut data).unwrap();
+    author.serialize(&mut data).unwrap();
+    baseline_account(runtime, data, IntelReportV1::SPACE)
+}
+
+// A CyberTeam written field by field in the original layout
+fn baseline_team(runtime: &mut Runtime, members: &[Pubkey]) -> Pubkey {
+    let mut data = CyberTeam::DISCRIMINATOR.to_vec();
+    "Case room".to_string().serialize(&mut data).unwrap();
+    members.to_vec().serialize(&mut data).unwrap();
+    baseline_account(runtime, data, CyberTeamV1::SPACE)
+}
+
+#[test]
+fn baseline_report_is_rejected_until_migrated() {
+    let mut runtime = Runtime::new();
+    let market = setup(&mut runtime);
+    let sigint = register_tag(&mut runtime, &market.admin, "SIGINT").unwrap();
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let report = baseline_report(&mut runtime, &author, &["sigint", "relay"]);
+
+    assert!(update_title(&mut runtime, &author, &report, "Renamed").is_err());
+    // Leaving out a Tag account drops the tag, so only the author may migrate
+    let stranger = runtime.wallet(LAMPORTS_PER_SOL);
+    assert_eq!(
+        migrate_report(&mut runtime, &stranger, &report, &[sigint]).unwrap_err(),
+        program_error(IntelMarketError::Unauthorized)
+    );
+    migrate_report(&mut runtime, &author, &report, &[sigint]).unwrap();
+    let migrated = &runtime.events::<AccountMigrated>()[0];
+    assert_eq!((migrated.from_version, migrated.to_version), (1, 2));
+
+    let state = runtime.get::<IntelReport>(&report);
+    assert_eq!((state.latitude, state.longitude), (48_856_613, 2_352_222));
+    assert_eq!(state.location_precision, LocationPrecision::Exact);
+    assert_eq!(state.geohash, geohash_encode(48_856_613, 2_352_222));
+    assert_eq!(state.tags, vec![sigint]);
+    assert_eq!(
+        state.content,
+        ReportContent::Inline("Seen forwarding beacons".to_string())
+    );
+    assert_eq!(state.observed_at, 1_700_000_000);
+    assert_eq!(state.author, author);
+    assert_eq!(
+        migrate_report(&mut runtime, &author, &report, &[]).unwrap_err(),
+        program_error(IntelMarketError::AlreadyMigrated)
+    );
+
+    update_title(&mut runtime, &author, &report, "Renamed").unwrap();
+    assert_eq!(runtime.get::<IntelReport>(&report).title, "Renamed");
+}
+
+#[test]
+fn accounts_of_another_version_are_rejected() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let report = create_report(&mut runtime, &author);
+    let mut data = runtime.account(&report).unwrap().data.clone();
+    data[8] = 1;
+    overwrite(&mut runtime, &report, data);
+    assert_eq!(
+        update_title(&mut runtime, &author, &report, "Renamed").unwrap_err(),
+        program_error(IntelMarketError::UnsupportedAccountVersion)
+    );
+    assert_eq!(
+        retract(&mut runtime, &author, &report).unwrap_err(),
+        program_error(IntelMarketError::UnsupportedAccountVersion)
+    );
+
+    let team = create_team(&mut runtime, &author);
+    let mut data = runtime.account(&team).unwrap().data.clone();
+    data[8] = 1;
+    overwrite(&mut runtime, &team, data);
+    assert_eq!(
+        invite(
+            &mut runtime,
+            &team,
+            &author,
+            &Pubkey::new_unique(),
+            TeamRole::Analyst
+        )
+        .unwrap_err(),
+        program_error(IntelMarketError::UnsupportedAccountVersion)
+    );
+}
+
+#[test]
+fn baseline_team_members_become_memberships() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let owner = runtime.wallet(LAMPORTS_PER_SOL);
+    let first = runtime.wallet(LAMPORTS_PER_SOL);
+    let second = runtime.wallet(LAMPORTS_PER_SOL);
+    let team = baseline_team(&mut runtime, &[owner, first, second]);
+
+    assert!(invite(
+        &mut runtime,
+        &team,
+        &owner,
+        &Pubkey::new_unique(),
+        TeamRole::Analyst
+    )
+    .is_err());
+    // Every member's membership is created, in the order of the original list
+    let payer = runtime.wallet(LAMPORTS_PER_SOL);
+    assert_eq!(
+        migrate_team(&mut runtime, &payer, &team, &[owner, first]).unwrap_err(),
+        anchor_lang::error::Error::from(ErrorCode::AccountNotEnoughKeys).into()
+    );
+    assert_eq!(
+        migrate_team(&mut runtime, &payer, &team, &[owner, second, first]).unwrap_err(),
+        anchor_lang::error::Error::from(ErrorCode::ConstraintSeeds).into()
+    );
+    migrate_team(&mut runtime, &payer, &team, &[owner, first, second]).unwrap();
+    let migrated = &runtime.events::<AccountMigrated>()[0];
+    assert_eq!((migrated.from_version, migrated.to_version), (1, 2));
+
+    let state = runtime.get::<CyberTeam>(&team);
+    assert_eq!(state.authority, owner);
+    assert_eq!(state.name, "Case room");
+    assert_eq!((state.member_count, state.voter_count), (3, 3));
+    assert_eq!(runtime.account(&team).unwrap().data.len(), CyberTeam::SPACE);
+    for (member, role) in [
+        (owner, TeamRole::Admin),
+        (first, TeamRole::Analyst),
+        (second, TeamRole::Analyst),
+    ] {
+        let membership = runtime.get::<TeamMembership>(&membership_pda(&team, &member));
+        assert_eq!((membership.member, membership.role), (member, role));
+        assert_eq!(membership.payer, payer);
+    }
+
+    invite(
+        &mut runtime,
+        &team,
+        &owner,
+        &Pubkey::new_unique(),
+        TeamRole::Analyst,
+    )
+    .unwrap();
+    leave(&mut runtime, &team, &second).unwrap();
+}
+
+#[test]
+fn baseline_team_without_members_cannot_be_migrated() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let payer = runtime.wallet(LAMPORTS_PER_SOL);
+    let team = baseline_team(&mut runtime, &[]);
+    assert_eq!(
+        migrate_team(&mut runtime, &payer, &team, &[]).unwrap_err(),
+        program_error(IntelMarketError::EmptyTeam)
+    );
+}
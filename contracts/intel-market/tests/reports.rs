@@ -0,0 +1,230 @@
+// Instruction-level tests of reports: tags, published locations and their GeoCell index,
+// sizes and team restriction.
+
+mod common;
+
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use common::*;
+use intel_market::{
+    geohash_encode, GeoCell, IntelMarketError, IntelReport, LocationPrecision, ReportContent, Tag,
+    TagEntry, TeamRole,
+};
+
+#[test]
+fn only_the_config_admin_registers_tags() {
+    let mut runtime = Runtime::new();
+    let market = setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+
+    assert_eq!(
+        register_tag(&mut runtime, &author, "sigint").unwrap_err(),
+        program_error(IntelMarketError::Unauthorized)
+    );
+    let id = register_tag(&mut runtime, &market.admin, "sigint").unwrap();
+    let tag = runtime.get::<Tag>(&tag_pda(id));
+    assert_eq!(tag.name, "SIGINT");
+    assert_eq!(tag.created_by, market.admin);
+    // Another spelling of a registered name is taken
+    assert!(register_tag(&mut runtime, &market.admin, "Sig-Int").is_err());
+}
+
+#[test]
+fn tag_entries_follow_the_report_tags() {
+    let mut runtime = Runtime::new();
+    let market = setup(&mut runtime);
+    let sigint = register_tag(&mut runtime, &market.admin, "sigint").unwrap();
+    let humint = register_tag(&mut runtime, &market.admin, "humint").unwrap();
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let anyone = runtime.wallet(LAMPORTS_PER_SOL);
+    let tagged = NewReport {
+        tags: vec![sigint],
+        ..NewReport::default()
+    };
+    let report = create_report_with(&mut runtime, &author, tagged).unwrap();
+
+    assert_eq!(
+        index_tag(&mut runtime, &author, &report, humint).unwrap_err(),
+        program_error(IntelMarketError::TagNotOnReport)
+    );
+    let entry = index_tag(&mut runtime, &author, &report, sigint).unwrap();
+    let state = runtime.get::<TagEntry>(&entry);
+    assert_eq!(
+        (state.tag, state.report, state.payer),
+        (sigint, report, author)
+    );
+    assert_eq!(runtime.get::<Tag>(&tag_pda(sigint)).report_count, 1);
+
+    // The entry stays while the report carries the tag
+    assert_eq!(
+        unindex_tag(&mut runtime, &anyone, &report, sigint).unwrap_err(),
+        program_error(IntelMarketError::TagStillOnReport)
+    );
+    retract(&mut runtime, &author, &report).unwrap();
+    let rent = runtime.lamports(&entry);
+    let author_before = runtime.lamports(&author);
+    unindex_tag(&mut runtime, &anyone, &report, sigint).unwrap();
+    assert!(runtime.account(&entry).is_none());
+    assert_eq!(runtime.lamports(&author), author_before + rent);
+    assert_eq!(runtime.get::<Tag>(&tag_pda(sigint)).report_count, 0);
+}
+
+#[test]
+fn coordinates_must_be_on_the_precision_grid() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let off_grid = program_error(IntelMarketError::CoordinatesNotOnGrid);
+
+    // The exact position must never reach the chain, not even to be rounded there
+    assert_eq!(
+        create_report_at(
+            &mut runtime,
+            &author,
+            48_858_370,
+            2_294_481,
+            LocationPrecision::City
+        )
+        .unwrap_err(),
+        off_grid
+    );
+    let report = create_report(&mut runtime, &author);
+
+    // Coarsening the precision alone leaves the stored position off the new grid
+    let country = LocationPrecision::Country;
+    assert_eq!(
+        update_location(&mut runtime, &author, &report, None, country).unwrap_err(),
+        off_grid
+    );
+    let coarsened = (
+        country.coarsen(48_850_000, IntelReport::MAX_LATITUDE),
+        country.coarsen(2_350_000, IntelReport::MAX_LONGITUDE),
+    );
+    update_location(&mut runtime, &author, &report, Some(coarsened), country).unwrap();
+    let stored = runtime.get::<IntelReport>(&report);
+    assert_eq!((stored.latitude, stored.longitude), (49_000_000, 2_000_000));
+
+    // Any finer precision still accepts the coarsened position
+    update_location(
+        &mut runtime,
+        &author,
+        &report,
+        None,
+        LocationPrecision::Exact,
+    )
+    .unwrap();
+}
+
+#[test]
+fn only_voting_members_publish_team_reports() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let owner = runtime.wallet(LAMPORTS_PER_SOL);
+    let team = create_team(&mut runtime, &owner);
+    let observer = add_member(&mut runtime, &team, &owner, TeamRole::Observer);
+    let analyst = add_member(&mut runtime, &team, &owner, TeamRole::Analyst);
+    let outsider = runtime.wallet(LAMPORTS_PER_SOL);
+    let for_team = || NewReport {
+        team: Some(team),
+        ..NewReport::default()
+    };
+
+    assert_eq!(
+        create_report_with(&mut runtime, &observer, for_team()).unwrap_err(),
+        program_error(IntelMarketError::Unauthorized)
+    );
+    assert!(create_report_with(&mut runtime, &outsider, for_team()).is_err());
+    let report = create_report_with(&mut runtime, &analyst, for_team()).unwrap();
+    assert_eq!(runtime.get::<IntelReport>(&report).team, Some(team));
+}
+
+#[test]
+fn reports_too_large_to_allocate_are_refused_up_front() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let oversized = NewReport {
+        content: ReportContent::Inline("b".repeat(MAX_PERMITTED_DATA_INCREASE)),
+        ..NewReport::default()
+    };
+    assert_eq!(
+        create_report_with(&mut runtime, &author, oversized).unwrap_err(),
+        program_error(IntelMarketError::ContentTooLong)
+    );
+}
+
+#[test]
+fn geo_cells_count_the_reports_inside_them() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let paris = (48_850_000, 2_350_000);
+    let london = (51_510_000, -120_000);
+    let cell_of = |(latitude, longitude)| GeoCell::cell_of(&geohash_encode(latitude, longitude));
+    let mismatch = program_error(IntelMarketError::GeoCellMismatch);
+
+    assert_eq!(
+        initialize_geo_cell(&mut runtime, &author, *b"u09a").unwrap_err(),
+        program_error(IntelMarketError::InvalidGeoCell)
+    );
+    let paris_cell = initialize_geo_cell(&mut runtime, &author, cell_of(paris)).unwrap();
+    let london_cell = initialize_geo_cell(&mut runtime, &author, cell_of(london)).unwrap();
+    let count = |runtime: &Runtime, cell| runtime.get::<GeoCell>(cell).report_count;
+
+    let in_cell = |geo_cell| NewReport {
+        geo_cell: Some(geo_cell),
+        ..NewReport::default()
+    };
+    assert_eq!(
+        create_report_with(&mut runtime, &author, in_cell(london_cell)).unwrap_err(),
+        mismatch
+    );
+    let report = create_report_with(&mut runtime, &author, in_cell(paris_cell)).unwrap();
+    assert!(runtime.get::<IntelReport>(&report).geo_indexed);
+    assert_eq!(count(&runtime, &paris_cell), 1);
+
+    // Moving to another cell takes both cells
+    let city = LocationPrecision::City;
+    assert_eq!(
+        update_location(&mut runtime, &author, &report, Some(london), city).unwrap_err(),
+        mismatch
+    );
+    move_report(
+        &mut runtime,
+        &author,
+        &report,
+        Some(london),
+        city,
+        Some(paris_cell),
+        Some(london_cell),
+    )
+    .unwrap();
+    assert_eq!(count(&runtime, &paris_cell), 0);
+    assert_eq!(count(&runtime, &london_cell), 1);
+
+    assert_eq!(
+        retract_from(&mut runtime, &author, &report, Some(paris_cell)).unwrap_err(),
+        mismatch
+    );
+    retract_from(&mut runtime, &author, &report, Some(london_cell)).unwrap();
+    assert_eq!(count(&runtime, &london_cell), 0);
+}
+
+#[test]
+fn observations_cannot_be_dated_in_the_future() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let author = runtime.wallet(LAMPORTS_PER_SOL);
+    let latest = runtime.clock.unix_timestamp + IntelReport::MAX_CLOCK_SKEW_SECS;
+    let observed = |observed_at| NewReport {
+        observed_at: Some(observed_at),
+        ..NewReport::default()
+    };
+
+    assert_eq!(
+        create_report_with(&mut runtime, &author, observed(latest + 1)).unwrap_err(),
+        program_error(IntelMarketError::ObservedInFuture)
+    );
+    // Clock drift within the allowance is accepted
+    let report = create_report_with(&mut runtime, &author, observed(latest)).unwrap();
+    assert_eq!(runtime.get::<IntelReport>(&report).observed_at, latest);
+}
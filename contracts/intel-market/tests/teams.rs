@@ -0,0 +1,287 @@
+// Instruction-level tests of team governance: invitations, proposals, approvals and execution.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use common::*;
+use intel_market::{
+    CyberTeam, IntelMarketError, ProposalAction, TeamInvitation, TeamMembership, TeamProposal,
+    TeamRole,
+};
+
+// A team with its owner, a delegated admin and an analyst
+fn team(runtime: &mut Runtime) -> (Pubkey, Pubkey, Pubkey, Pubkey) {
+    setup(runtime);
+    let owner = runtime.wallet(LAMPORTS_PER_SOL);
+    let team = create_team(runtime, &owner);
+    let admin = add_member(runtime, &team, &owner, TeamRole::Admin);
+    let analyst = add_member(runtime, &team, &owner, TeamRole::Analyst);
+    (team, owner, admin, analyst)
+}
+
+#[test]
+fn team_names_are_limited_at_creation() {
+    let mut runtime = Runtime::new();
+    setup(&mut runtime);
+    let owner = runtime.wallet(LAMPORTS_PER_SOL);
+    let longest = "x".repeat(CyberTeam::MAX_NAME_LEN);
+    assert_eq!(
+        create_named_team(&mut runtime, &owner, &format!("{longest}x")).unwrap_err(),
+        program_error(IntelMarketError::TeamNameTooLong)
+    );
+    let team = create_named_team(&mut runtime, &owner, &longest).unwrap();
+    assert_eq!(runtime.get::<CyberTeam>(&team).name, longest);
+}
+
+#[test]
+fn invitations_are_accepted_or_declined_by_the_invitee() {
+    let mut runtime = Runtime::new();
+    let (team, owner, _, analyst) = team(&mut runtime);
+    let invitee = runtime.wallet(LAMPORTS_PER_SOL);
+    let other = runtime.wallet(LAMPORTS_PER_SOL);
+    invite(&mut runtime, &team, &owner, &invitee, TeamRole::Observer).unwrap();
+    invite(&mut runtime, &team, &owner, &other, TeamRole::Analyst).unwrap();
+
+    // Others cannot close it before it expires
+    assert_eq!(
+        close_invitation(&mut runtime, &team, &invitee, &analyst).unwrap_err(),
+        program_error(IntelMarketError::InvitationNotExpired)
+    );
+    accept(&mut runtime, &team, &invitee).unwrap();
+    let membership = runtime.get::<TeamMembership>(&membership_pda(&team, &invitee));
+    assert_eq!(membership.role, TeamRole::Observer);
+    assert!(runtime.account(&invitation_pda(&team, &invitee)).is_none());
+    assert_eq!(runtime.get::<CyberTeam>(&team).member_count, 4);
+
+    // Declining refunds the inviting admin
+    let invitation = invitation_pda(&team, &other);
+    let rent = runtime.lamports(&invitation);
+    let owner_before = runtime.lamports(&owner);
+    close_invitation(&mut runtime, &team, &other, &other).unwrap();
+    assert!(runtime.account(&invitation).is_none());
+    assert_eq!(runtime.lamports(&owner), owner_before + rent);
+    assert!(runtime.account(&membership_pda(&team, &other)).is_none());
+}
+
+#[test]
+fn expired_invitations_cannot_be_accepted() {
+    let mut runtime = Runtime::new();
+    let (team, owner, _, analyst) = team(&mut runtime);
+    let invitee = runtime.wallet(LAMPORTS_PER_SOL);
+    invite(&mut runtime, &team, &owner, &invitee, TeamRole::Analyst).unwrap();
+
+    runtime.warp(TeamInvitation::TTL_SECS);
+    assert_eq!(
+        accept(&mut runtime, &team, &invitee).unwrap_err(),
+        program_error(IntelMarketError::InvitationExpired)
+    );
+    // Anyone can clear it once expired
+    close_invitation(&mut runtime, &team, &invitee, &analyst).unwrap();
+    assert!(runtime.account(&invitation_pda(&team, &invitee)).is_none());
+    assert_eq!(runtime.get::<CyberTeam>(&team).member_count, 3);
+}
+
+#[test]
+fn analyst_cannot_act_alone_at_threshold_one() {
+    let mut runtime = Runtime::new();
+    let (team, owner, _, analyst) = team(&mut runtime);
+    let unauthorized = program_error(IntelMarketError::Unauthorized);
+
+    let promote = ProposalAction::ChangeRole {
+        member: analyst,
+        role: TeamRole::Admin,
+    };
+    assert_eq!(
+        propose(&mut runtime, &team, &analyst, promote, Some(analyst)).unwrap_err(),
+        unauthorized
+    );
+    let add_admin = ProposalAction::AddMember {
+        member: Pubkey::new_unique(),
+        role: TeamRole::Admin,
+    };
+    assert_eq!(
+        propose(&mut runtime, &team, &analyst, add_admin, None).unwrap_err(),
+        unauthorized
+    );
+    let rename = ProposalAction::Rename {
+        name: "Taken over".to_string(),
+    };
+    assert_eq!(
+        propose(&mut runtime, &team, &analyst, rename, None).unwrap_err(),
+        unauthorized
+    );
+    let remove_owner = ProposalAction::RemoveMember { member: owner };
+    assert_eq!(
+        propose(&mut runtime, &team, &analyst, remove_owner, Some(owner)).unwrap_err(),
+        program_error(IntelMarketError::CannotRemoveOwner)
+    );
+}
+
+#[test]
+fn delegated_admin_proposals_follow_direct_permissions() {
+    let mut runtime = Runtime::new();
+    let (team, owner, admin, analyst) = team(&mut runtime);
+    let peer = add_member(&mut runtime, &team, &owner, TeamRole::Admin);
+    let unauthorized = program_error(IntelMarketError::Unauthorized);
+
+    // Another admin's removal needs the owner, and the target's role is always checked
+    let remove_peer = ProposalAction::RemoveMember { member: peer };
+    assert_eq!(
+        propose(&mut runtime, &team, &admin, remove_peer.clone(), Some(peer)).unwrap_err(),
+        unauthorized
+    );
+    assert_eq!(
+        propose(&mut runtime, &team, &admin, remove_peer, None).unwrap_err(),
+        unauthorized
+    );
+    assert_eq!(
+        propose(
+            &mut runtime,
+            &team,
+            &admin,
+            ProposalAction::RemoveMember { member: analyst },
+            Some(peer),
+        )
+        .unwrap_err(),
+        program_error(IntelMarketError::ProposalActionMismatch)
+    );
+
+    let remove_analyst = ProposalAction::RemoveMember { member: analyst };
+    let proposal = propose(&mut runtime, &team, &admin, remove_analyst, Some(analyst)).unwrap();
+    execute(&mut runtime, &team, &proposal, &[admin]).unwrap();
+    assert!(runtime.account(&membership_pda(&team, &analyst)).is_none());
+    assert_eq!(runtime.get::<CyberTeam>(&team).member_count, 3);
+}
+
+#[test]
+fn execution_rechecks_the_approver_at_threshold_one() {
+    let mut runtime = Runtime::new();
+    let (team, owner, admin, _) = team(&mut runtime);
+    let invite = ProposalAction::AddMember {
+        member: Pubkey::new_unique(),
+        role: TeamRole::Analyst,
+    };
+    let proposal = propose(&mut runtime, &team, &admin, invite, None).unwrap();
+
+    // The owner demotes the proposing admin before the proposal is executed
+    let demote = ProposalAction::ChangeRole {
+        member: admin,
+        role: TeamRole::Analyst,
+    };
+    let demotion = propose(&mut runtime, &team, &owner, demote, Some(admin)).unwrap();
+    execute(&mut runtime, &team, &demotion, &[owner]).unwrap();
+    assert_eq!(
+        runtime
+            .get::<TeamMembership>(&membership_pda(&team, &admin))
+            .role,
+        TeamRole::Analyst
+    );
+
+    assert_eq!(
+        execute(&mut runtime, &team, &proposal, &[admin]).unwrap_err(),
+        program_error(IntelMarketError::Unauthorized)
+    );
+    // Memberships of members who did not approve do not count
+    let not_approved = program_error(IntelMarketError::ProposalNotApproved);
+    assert_eq!(
+        execute(&mut runtime, &team, &proposal, &[]).unwrap_err(),
+        not_approved
+    );
+    assert_eq!(
+        execute(&mut runtime, &team, &proposal, &[owner]).unwrap_err(),
+        not_approved
+    );
+    approve(&mut runtime, &team, &owner, &proposal).unwrap();
+    execute(&mut runtime, &team, &proposal, &[admin, owner]).unwrap();
+}
+
+// Raises the team's threshold through a proposal from the owner
+fn set_threshold(runtime: &mut Runtime, team: &Pubkey, owner: &Pubkey, threshold: u8) {
+    let action = ProposalAction::SetThreshold { threshold };
+    let proposal = propose(runtime, team, owner, action, None).unwrap();
+    execute(runtime, team, &proposal, &[*owner]).unwrap();
+}
+
+#[test]
+fn approvals_lapse_when_the_approver_leaves() {
+    let mut runtime = Runtime::new();
+    let (team, owner, admin, analyst) = team(&mut runtime);
+    set_threshold(&mut runtime, &team, &owner, 2);
+
+    let rename = ProposalAction::Rename {
+        name: "Incident 42".to_string(),
+    };
+    let proposal = propose(&mut runtime, &team, &admin, rename, None).unwrap();
+    approve(&mut runtime, &team, &analyst, &proposal).unwrap();
+    leave(&mut runtime, &team, &analyst).unwrap();
+    let not_approved = program_error(IntelMarketError::ProposalNotApproved);
+    assert_eq!(
+        execute(&mut runtime, &team, &proposal, &[admin, analyst]).unwrap_err(),
+        not_approved
+    );
+    // Listing an approver twice does not count twice either
+    assert_eq!(
+        execute(&mut runtime, &team, &proposal, &[admin, admin]).unwrap_err(),
+        not_approved
+    );
+
+    approve(&mut runtime, &team, &owner, &proposal).unwrap();
+    execute(&mut runtime, &team, &proposal, &[admin, owner]).unwrap();
+    assert_eq!(runtime.get::<CyberTeam>(&team).name, "Incident 42");
+}
+
+#[test]
+fn voting_members_cannot_drop_below_the_threshold() {
+    let mut runtime = Runtime::new();
+    let (team, owner, admin, analyst) = team(&mut runtime);
+    let observer = add_member(&mut runtime, &team, &owner, TeamRole::Observer);
+    assert_eq!(runtime.get::<CyberTeam>(&team).voter_count, 3);
+    assert_eq!(runtime.get::<CyberTeam>(&team).member_count, 4);
+
+    // Observers are members but not voters
+    let too_high = ProposalAction::SetThreshold { threshold: 4 };
+    let proposal = propose(&mut runtime, &team, &owner, too_high, None).unwrap();
+    assert_eq!(
+        execute(&mut runtime, &team, &proposal, &[owner]).unwrap_err(),
+        program_error(IntelMarketError::ThresholdExceedsVoters)
+    );
+    set_threshold(&mut runtime, &team, &owner, 3);
+
+    let exceeds = program_error(IntelMarketError::ThresholdExceedsVoters);
+    assert_eq!(leave(&mut runtime, &team, &analyst).unwrap_err(), exceeds);
+    leave(&mut runtime, &team, &observer).unwrap();
+
+    let demote = ProposalAction::ChangeRole {
+        member: admin,
+        role: TeamRole::Observer,
+    };
+    let proposal = propose(&mut runtime, &team, &owner, demote, Some(admin)).unwrap();
+    approve(&mut runtime, &team, &admin, &proposal).unwrap();
+    approve(&mut runtime, &team, &analyst, &proposal).unwrap();
+    assert_eq!(
+        execute(&mut runtime, &team, &proposal, &[owner, admin, analyst]).unwrap_err(),
+        exceeds
+    );
+    let state = runtime.get::<CyberTeam>(&team);
+    assert_eq!((state.voter_count, state.member_count), (3, 3));
+}
+
+#[test]
+fn approvals_stop_at_the_proposal_capacity() {
+    let mut runtime = Runtime::new();
+    let (team, owner, _, _) = team(&mut runtime);
+    let rename = ProposalAction::Rename {
+        name: "Incident 42".to_string(),
+    };
+    let proposal = propose(&mut runtime, &team, &owner, rename, None).unwrap();
+    while runtime.get::<TeamProposal>(&proposal).approvals.len() < TeamProposal::MAX_APPROVALS {
+        let analyst = add_member(&mut runtime, &team, &owner, TeamRole::Analyst);
+        approve(&mut runtime, &team, &analyst, &proposal).unwrap();
+    }
+
+    let late = add_member(&mut runtime, &team, &owner, TeamRole::Analyst);
+    assert_eq!(
+        approve(&mut runtime, &team, &late, &proposal).unwrap_err(),
+        program_error(IntelMarketError::TooManyApprovals)
+    );
+}
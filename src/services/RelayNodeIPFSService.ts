@@ -13,6 +13,7 @@
 import ipfsService, { IPFSUploadResult } from './IPFSService';
 import { IntelPackage, CyberTeam, CyberInvestigation, Evidence } from '../types/cyberInvestigation';
 import { conditionalLog } from '../utils/featureFlags';
+import { sha256 } from '@noble/hashes/sha256';
 
 // Event listener type for RelayNode events
 type EventListener = (data: RelayNodeEvent) => void;
@@ -110,6 +111,28 @@ interface TeamContentMetadata {
   }>;
 }
 
+/**
+ * Off-chain body reference stored in an on-chain IntelReport
+ * (`ReportContent::OffChain` in contracts/intel-market/intel_report.rs)
+ */
+export interface OffChainReportContent {
+  cid: string;
+  sha256: Uint8Array | number[]; // 32-byte digest as returned by the Anchor client
+  length: number;
+}
+
+/**
+ * Check that a fetched IntelReport body matches the digest and length
+ * committed on-chain
+ */
+export function verifyIntelReportContent(payload: Uint8Array, onChain: OffChainReportContent): boolean {
+  if (payload.length !== onChain.length || onChain.sha256.length !== 32) {
+    return false;
+  }
+  const digest = sha256(payload);
+  return digest.every((byte, i) => byte === onChain.sha256[i]);
+}
+
 export class RelayNodeIPFSService implements EventEmitter {
   private static instance: RelayNodeIPFSService;
   private fallbackIPFS: typeof ipfsService;
@@ -529,6 +552,17 @@ export class RelayNodeIPFSService implements EventEmitter {
     }
   }
 
+  /**
+   * Download an off-chain IntelReport body and verify it against the on-chain hash
+   */
+  public async downloadIntelReportContent(onChain: OffChainReportContent): Promise<Uint8Array> {
+    const payload = await this.downloadContent(onChain.cid);
+    if (!verifyIntelReportContent(payload, onChain)) {
+      throw new Error(`IntelReport content does not match on-chain hash: ${onChain.cid}`);
+    }
+    return payload;
+  }
+
   /**
    * Download from fallback IPFS service
    */